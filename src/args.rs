@@ -29,10 +29,27 @@ pub enum Command {
     /// directory.
     #[command(visible_alias = "c")]
     Create(Create),
+    /// Kill the tmux sessions of all specified directories or names or the
+    /// current tmux session.
+    #[command(visible_alias = "k")]
+    Kill(Kill),
+    /// List all tmux sessions.
+    #[command(visible_alias = "ls")]
+    List(List),
     #[command(hide = true)]
-    Select,
+    Select(Select),
     #[command(hide = true)]
     Last,
+    #[command(hide = true)]
+    Back,
+    #[command(hide = true)]
+    Forward,
+    /// Trust the project configuration files of all specified directories or
+    /// the current directory.
+    Trust(Trust),
+    /// Import directories into the frecency database from the output of
+    /// `zoxide query -ls`.
+    ImportZoxide(ImportZoxide),
 }
 
 #[derive(Debug, Clone, clap::Args)]
@@ -47,3 +64,64 @@ pub struct Create {
     #[arg(short, long)]
     pub detached: bool,
 }
+
+#[derive(Debug, Clone, clap::Args)]
+pub struct Kill {
+    /// Directories or names of the tmux sessions to kill.
+    #[arg(conflicts_with_all = ["all", "others"])]
+    pub targets: Vec<String>,
+    /// Kill all tmux sessions.
+    #[arg(short, long, conflicts_with = "others")]
+    pub all: bool,
+    /// Kill all tmux sessions except for the current one.
+    #[arg(short, long)]
+    pub others: bool,
+}
+
+#[derive(Debug, Clone, clap::Args)]
+pub struct List {
+    /// Set output format: `plain` for tab-separated values, `json` or a tmux
+    /// format string.
+    #[arg(short, long, default_value = "plain")]
+    pub format: String,
+    /// Sort tmux sessions, by default by their path or by their score if a
+    /// pattern is specified.
+    #[arg(short, long, value_enum)]
+    pub sort: Option<ListSort>,
+    /// Reverse the order of tmux sessions.
+    #[arg(short, long)]
+    pub reverse: bool,
+    /// Only list tmux sessions whose path, as displayed in the session
+    /// selector, matches the pattern in the configured matching mode, i.e.
+    /// fuzzy, exact, prefix or regex.
+    #[arg(short, long)]
+    pub pattern: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, clap::ValueEnum)]
+pub enum ListSort {
+    Path,
+    Name,
+    /// Most recent activity first.
+    Activity,
+}
+
+#[derive(Debug, Clone, clap::Args)]
+pub struct Select {
+    /// Switch to the selected session only on exit and kill the tmux pane the
+    /// session selector runs in.
+    #[arg(long)]
+    pub temporary_pane: bool,
+}
+
+#[derive(Debug, Clone, clap::Args)]
+pub struct Trust {
+    /// Directories to trust the project configuration files of.
+    pub paths: Vec<PathBuf>,
+}
+
+#[derive(Debug, Clone, clap::Args)]
+pub struct ImportZoxide {
+    /// File to read from instead of stdin.
+    pub file: Option<PathBuf>,
+}
@@ -1,43 +1,60 @@
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use crate::{
     args::{self, Args},
     config::Config,
-    tmux::{self, Session},
+    frecency,
+    tmux::{self, History, Session},
 };
 
 pub fn create(args: &Args, args_create: &args::Create, config: &Config) -> anyhow::Result<()> {
-    let current_session_opt = Session::current(args.target_client.as_ref()).ok().flatten();
-
-    let paths_map_fn = |path: &PathBuf| {
-        if !path.try_exists()? {
-            if !args_create.create_dirs {
-                anyhow::bail!("path '{}' does not exist", path.to_string_lossy());
-            }
-            std::fs::create_dir_all(path)?;
-        }
-        if path.is_file() {
-            anyhow::bail!("path '{}' points to a file", path.to_string_lossy());
-        }
-        let path = path.canonicalize()?;
-        Ok(path)
-    };
     let mut paths = args_create
         .paths
         .iter()
-        .map(paths_map_fn)
+        .map(|path| prepare_path(path, args_create.create_dirs))
         .collect::<Result<Vec<_>, _>>()?;
     if paths.is_empty() {
         paths.push(std::env::current_dir()?);
     }
 
+    create_sessions(args, &paths, args_create.detached, config)
+}
+
+/// Checks that the path points to a directory, creating it first if it does
+/// not exist and `create_dirs` is set, and returns its canonical path.
+pub fn prepare_path(path: &Path, create_dirs: bool) -> anyhow::Result<PathBuf> {
+    if !path.try_exists()? {
+        if !create_dirs {
+            anyhow::bail!("path '{}' does not exist", path.to_string_lossy());
+        }
+        std::fs::create_dir_all(path)?;
+    }
+    if path.is_file() {
+        anyhow::bail!("path '{}' points to a file", path.to_string_lossy());
+    }
+    let path = path.canonicalize()?;
+    Ok(path)
+}
+
+/// Creates sessions for all given canonical paths, unless they already exist,
+/// and switches to the last newly created or otherwise the first existing
+/// one.
+pub fn create_sessions(
+    args: &Args,
+    paths: &[PathBuf],
+    detached: bool,
+    config: &Config,
+) -> anyhow::Result<()> {
+    let current_session_opt = Session::current(args.target_client.as_ref()).ok().flatten();
+
     let mut session_to_switch_to_opt = Option::<Session>::None;
-    for path in &paths {
-        let (session, has_existed) = Session::new(path)?;
+    for path in paths {
+        let (session, has_existed) = Session::new(path, config)?;
+        frecency::record(path)?;
 
         tmux::set_up(config)?;
 
-        if args_create.detached {
+        if detached {
             continue;
         }
         if has_existed {
@@ -50,11 +67,7 @@ pub fn create(args: &Args, args_create: &args::Create, config: &Config) -> anyho
     }
 
     if let Some(session_to_switch_to) = session_to_switch_to_opt {
-        if let Some(current_session) = current_session_opt
-            && current_session != session_to_switch_to
-        {
-            current_session.save_as_last()?;
-        }
+        History::record_switch(current_session_opt.as_ref(), &session_to_switch_to)?;
         session_to_switch_to.switch_to(args.target_client.as_ref())?;
     }
 
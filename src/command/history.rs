@@ -0,0 +1,31 @@
+use crate::{
+    args::Args,
+    frecency,
+    tmux::{self, History, Session},
+};
+
+pub fn back(args: &Args) -> anyhow::Result<()> {
+    navigate(args, History::go_back)
+}
+
+pub fn forward(args: &Args) -> anyhow::Result<()> {
+    navigate(args, History::go_forward)
+}
+
+fn navigate(args: &Args, go_fn: fn(&mut History) -> Option<Session>) -> anyhow::Result<()> {
+    tmux::assert_in_session()?;
+
+    let mut history = History::read()?;
+    // The current session might have been switched to without tmuxion, in
+    // which case it is added to the history first.
+    if let Some(current_session) = Session::current(args.target_client.as_ref())?
+        && history.current() != Some(&current_session)
+    {
+        history.push(&current_session);
+    }
+    if let Some(session) = go_fn(&mut history) {
+        session.switch_to(args.target_client.as_ref())?;
+        frecency::record(session.path())?;
+    }
+    history.write()
+}
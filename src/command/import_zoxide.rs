@@ -0,0 +1,32 @@
+use std::{io::Read as _, path::PathBuf};
+
+use crate::{args, frecency::Frecency};
+
+pub fn import_zoxide(args_import_zoxide: &args::ImportZoxide) -> anyhow::Result<()> {
+    let content = if let Some(file) = &args_import_zoxide.file {
+        std::fs::read_to_string(file)?
+    } else {
+        let mut content = String::new();
+        std::io::stdin().read_to_string(&mut content)?;
+        content
+    };
+
+    let mut frecency = Frecency::read()?;
+    for line in content.lines() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        let (score, path) = line.split_once(char::is_whitespace).ok_or_else(|| {
+            anyhow::format_err!("invalid line '{line}', expected '<score> <path>'")
+        })?;
+        let score = score.parse::<f64>()?;
+        let Ok(path) = PathBuf::from(path.trim_start()).canonicalize() else {
+            continue;
+        };
+        if path.is_dir() {
+            frecency.add(&path, score);
+        }
+    }
+    frecency.write()
+}
@@ -0,0 +1,93 @@
+use std::path::Path;
+
+use crate::{
+    args::{self, Args},
+    tmux::{History, Session},
+};
+
+pub fn kill(args: &Args, args_kill: &args::Kill) -> anyhow::Result<()> {
+    let sessions = Session::all()?;
+    let current_session_opt = Session::current(args.target_client.as_ref()).ok().flatten();
+
+    let sessions_to_kill = if args_kill.all {
+        sessions.clone()
+    } else if args_kill.others {
+        sessions
+            .iter()
+            .filter(|session| Some(*session) != current_session_opt.as_ref())
+            .cloned()
+            .collect()
+    } else if args_kill.targets.is_empty() {
+        let current_session = current_session_opt
+            .clone()
+            .ok_or(anyhow::format_err!("current session is not managed"))?;
+        vec![current_session]
+    } else {
+        let targets_map_fn = |target: &String| {
+            let path_opt = Path::new(target).canonicalize().ok();
+            sessions
+                .iter()
+                .find(|session| {
+                    path_opt.as_ref() == Some(session.path()) || session.name() == target
+                })
+                .cloned()
+                .ok_or(anyhow::format_err!("no session found for '{target}'"))
+        };
+        args_kill
+            .targets
+            .iter()
+            .map(targets_map_fn)
+            .collect::<Result<Vec<_>, _>>()?
+    };
+
+    kill_sessions(
+        args,
+        &sessions,
+        &sessions_to_kill,
+        Session::last()?.as_ref(),
+    )?;
+    Ok(())
+}
+
+/// Kills the given sessions. If the current session is going to be killed, the
+/// client is switched away from it first, so it is not detached, preferring
+/// the given session over the next session that is not going to be killed.
+/// Returns the session, which has been switched to.
+pub fn kill_sessions(
+    args: &Args,
+    sessions: &[Session],
+    sessions_to_kill: &[Session],
+    preferred_session_opt: Option<&Session>,
+) -> anyhow::Result<Option<Session>> {
+    let current_session_opt = Session::current(args.target_client.as_ref()).ok().flatten();
+    let mut session_switched_to_opt = None;
+    if let Some(current_session) = &current_session_opt
+        && sessions_to_kill.contains(current_session)
+    {
+        let current_session_pos = sessions
+            .iter()
+            .position(|session| session == current_session)
+            .unwrap_or_default();
+        let session_to_switch_to_opt = preferred_session_opt
+            .into_iter()
+            .chain(
+                sessions
+                    .iter()
+                    .cycle()
+                    .skip(current_session_pos + 1)
+                    .take(sessions.len()),
+            )
+            .find(|session| !sessions_to_kill.contains(session));
+        if let Some(session_to_switch_to) = session_to_switch_to_opt {
+            History::record_switch(None, session_to_switch_to)?;
+            session_to_switch_to.switch_to(args.target_client.as_ref())?;
+            session_switched_to_opt = Some(session_to_switch_to.clone());
+        }
+    }
+
+    for session in sessions_to_kill {
+        session.kill()?;
+    }
+
+    Ok(session_switched_to_opt)
+}
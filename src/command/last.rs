@@ -1,6 +1,7 @@
 use crate::{
     args::Args,
-    tmux::{self, Session},
+    frecency,
+    tmux::{self, History, Session},
 };
 
 pub fn last(args: &Args) -> anyhow::Result<()> {
@@ -9,10 +10,9 @@ pub fn last(args: &Args) -> anyhow::Result<()> {
     let last_session_opt = Session::last()?;
     let current_session_opt = Session::current(args.target_client.as_ref())?;
     if let Some(last_session) = last_session_opt {
-        if let Some(current_session) = current_session_opt {
-            current_session.save_as_last()?;
-        }
+        History::record_switch(current_session_opt.as_ref(), &last_session)?;
         last_session.switch_to(args.target_client.as_ref())?;
+        frecency::record(last_session.path())?;
     }
 
     Ok(())
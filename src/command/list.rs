@@ -0,0 +1,96 @@
+use std::cmp::Reverse;
+
+use ahash::HashMap;
+use tmux_interface::{ListSessions, Tmux};
+
+use crate::{
+    args::{self, ListSort},
+    config::Config,
+    matching, path,
+    tmux::Session,
+};
+
+pub fn list(args_list: &args::List, config: &Config) -> anyhow::Result<()> {
+    let mut sessions = Session::all()?;
+
+    if let Some(pattern) = &args_list.pattern {
+        matching::validate(pattern, config.session_selector.matching.mode)?;
+        let paths = sessions
+            .iter()
+            .map(|session| path::display(session.path(), &config.session_selector.paths))
+            .collect::<Vec<_>>();
+        let mut sessions_opts = sessions.into_iter().map(Some).collect::<Vec<_>>();
+        // The sessions are matched like in the session selector.
+        let matches = matching::matches(
+            pattern,
+            &paths,
+            &config.session_selector.matching,
+            config.session_selector.matching.mode,
+        );
+        sessions = matches
+            .iter()
+            .filter_map(|m| sessions_opts[m.index_in_haystack].take())
+            .collect();
+    }
+    match args_list.sort {
+        Some(ListSort::Path) => sessions.sort(),
+        Some(ListSort::Name) => sessions.sort_by(|a, b| a.name().cmp(b.name())),
+        Some(ListSort::Activity) => sessions.sort_by_key(|session| Reverse(session.activity())),
+        None => (),
+    }
+    if args_list.reverse {
+        sessions.reverse();
+    }
+
+    match args_list.format.as_str() {
+        "plain" => {
+            for session in &sessions {
+                println!(
+                    "{}\t{}\t{}\t{}\t{}\t{}",
+                    session.id(),
+                    session.name(),
+                    session.path().to_string_lossy(),
+                    session.windows(),
+                    session.attached(),
+                    session.activity()
+                );
+            }
+        }
+        "json" => {
+            let sessions_json = sessions
+                .iter()
+                .map(|session| {
+                    serde_json::json!({
+                        "id": session.id(),
+                        "name": session.name(),
+                        "path": session.path(),
+                        "windows": session.windows(),
+                        "attached": session.attached(),
+                        "activity": session.activity(),
+                    })
+                })
+                .collect::<Vec<_>>();
+            println!("{}", serde_json::to_string_pretty(&sessions_json)?);
+        }
+        template => {
+            // Let tmux expand the template as a format string for all sessions
+            // and print the lines of the listed sessions in order.
+            let output = Tmux::with_command(
+                ListSessions::new().format(format!("#{{session_id}} {template}")),
+            )
+            .output()?
+            .to_string();
+            let lines = output
+                .lines()
+                .filter_map(|line| line.split_once(' '))
+                .collect::<HashMap<_, _>>();
+            for session in &sessions {
+                if let Some(line) = lines.get(session.id()) {
+                    println!("{line}");
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
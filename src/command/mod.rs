@@ -1,5 +1,19 @@
 mod create;
+mod history;
+mod import_zoxide;
+mod kill;
 mod last;
+mod list;
 mod select;
+mod trust;
 
-pub use self::{create::create, last::last, select::select};
+pub use self::{
+    create::{create, create_sessions, prepare_path},
+    history::{back, forward},
+    import_zoxide::import_zoxide,
+    kill::{kill, kill_sessions},
+    last::last,
+    list::list,
+    select::select,
+    trust::trust,
+};
@@ -0,0 +1,211 @@
+use ratatui::{
+    style::{Color, Modifier, Style},
+    text::{Line, Span, Text},
+};
+
+const ESC: char = '\x1b';
+const BEL: char = '\x07';
+
+/// Converts text containing ANSI escape sequences, as printed by
+/// `capture-pane -e`, into styled text. Select graphic rendition sequences are
+/// converted into styles, while all other escape sequences are dropped.
+pub fn to_text(s: &str) -> Text<'static> {
+    let mut style = Style::new();
+    let lines = s
+        .lines()
+        .map(|line| {
+            let mut spans = Vec::new();
+            let mut content = String::new();
+            let mut chars = line.chars().peekable();
+            while let Some(c) = chars.next() {
+                if c != ESC {
+                    content.push(c);
+                    continue;
+                }
+                match chars.next() {
+                    // Control sequence introducer.
+                    Some('[') => {
+                        let mut params = String::new();
+                        let mut final_byte_opt = None;
+                        for c in chars.by_ref() {
+                            if ('\x40'..='\x7e').contains(&c) {
+                                final_byte_opt = Some(c);
+                                break;
+                            }
+                            params.push(c);
+                        }
+                        if final_byte_opt == Some('m') {
+                            if !content.is_empty() {
+                                spans.push(Span::styled(std::mem::take(&mut content), style));
+                            }
+                            style = apply_sgr(style, &params);
+                        }
+                    }
+                    // Operating system command, e.g. a hyperlink, which is
+                    // terminated by either BEL or ST.
+                    Some(']') => {
+                        while let Some(c) = chars.next() {
+                            if c == BEL || (c == ESC && chars.next_if_eq(&'\\').is_some()) {
+                                break;
+                            }
+                        }
+                    }
+                    _ => (),
+                }
+            }
+            if !content.is_empty() {
+                spans.push(Span::styled(content, style));
+            }
+            Line::from(spans)
+        })
+        .collect::<Vec<_>>();
+    Text::from(lines)
+}
+
+fn apply_sgr(mut style: Style, params: &str) -> Style {
+    let mut params = params.split(';');
+    while let Some(param) = params.next() {
+        let mut subparams = param.split(':');
+        let code = subparams
+            .next()
+            .and_then(|code| code.parse::<u8>().ok())
+            .unwrap_or_default();
+        style = match code {
+            0 => Style::new(),
+            1 => style.add_modifier(Modifier::BOLD),
+            2 => style.add_modifier(Modifier::DIM),
+            3 => style.add_modifier(Modifier::ITALIC),
+            // Underline styles are passed as subparameter, where `4:0` turns
+            // the underline off.
+            4 if subparams.next() == Some("0") => style.remove_modifier(Modifier::UNDERLINED),
+            4 => style.add_modifier(Modifier::UNDERLINED),
+            5 => style.add_modifier(Modifier::SLOW_BLINK),
+            6 => style.add_modifier(Modifier::RAPID_BLINK),
+            7 => style.add_modifier(Modifier::REVERSED),
+            8 => style.add_modifier(Modifier::HIDDEN),
+            9 => style.add_modifier(Modifier::CROSSED_OUT),
+            22 => style.remove_modifier(Modifier::BOLD | Modifier::DIM),
+            23 => style.remove_modifier(Modifier::ITALIC),
+            24 => style.remove_modifier(Modifier::UNDERLINED),
+            25 => style.remove_modifier(Modifier::SLOW_BLINK | Modifier::RAPID_BLINK),
+            27 => style.remove_modifier(Modifier::REVERSED),
+            28 => style.remove_modifier(Modifier::HIDDEN),
+            29 => style.remove_modifier(Modifier::CROSSED_OUT),
+            30..=37 => style.fg(Color::Indexed(code - 30)),
+            40..=47 => style.bg(Color::Indexed(code - 40)),
+            90..=97 => style.fg(Color::Indexed(code - 90 + 8)),
+            100..=107 => style.bg(Color::Indexed(code - 100 + 8)),
+            39 => style.fg(Color::Reset),
+            49 => style.bg(Color::Reset),
+            38 | 48 | 58 => {
+                // Extended colors are either passed as subparameters, e.g.
+                // `38:5:n`, or as the following parameters, e.g. `38;5;n`.
+                let mut subparams = subparams.collect::<Vec<_>>();
+                // Direct colors passed as subparameters may contain a color
+                // space identifier, e.g. `38:2::r:g:b`.
+                if subparams.len() == 5 && subparams[0] == "2" {
+                    subparams.remove(1);
+                }
+                let color_opt = if subparams.is_empty() {
+                    parse_extended_color(&mut params)
+                } else {
+                    parse_extended_color(&mut subparams.into_iter())
+                };
+                match (code, color_opt) {
+                    (38, Some(color)) => style.fg(color),
+                    (48, Some(color)) => style.bg(color),
+                    _ => style,
+                }
+            }
+            _ => style,
+        };
+    }
+    style
+}
+
+fn parse_extended_color<'a>(params: &mut impl Iterator<Item = &'a str>) -> Option<Color> {
+    let mut next_fn = || params.next().and_then(|param| param.parse::<u8>().ok());
+    match next_fn()? {
+        5 => next_fn().map(Color::Indexed),
+        2 => Some(Color::Rgb(next_fn()?, next_fn()?, next_fn()?)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spans(s: &str) -> Vec<(String, Style)> {
+        to_text(s).lines[0]
+            .spans
+            .iter()
+            .map(|span| (span.content.to_string(), span.style))
+            .collect()
+    }
+
+    #[test]
+    fn sgr_reset_clears_the_style() {
+        assert_eq!(
+            spans("\x1b[1;31mfoo\x1b[0mbar\x1b[33;mbaz"),
+            vec![
+                (
+                    "foo".to_owned(),
+                    Style::new()
+                        .add_modifier(Modifier::BOLD)
+                        .fg(Color::Indexed(1))
+                ),
+                ("bar".to_owned(), Style::new()),
+                // An empty parameter is a reset as well.
+                ("baz".to_owned(), Style::new()),
+            ]
+        );
+    }
+
+    #[test]
+    fn indexed_colors_are_parsed_from_parameters_and_subparameters() {
+        assert_eq!(
+            spans("\x1b[38;5;208ma\x1b[48:5:17mb"),
+            vec![
+                ("a".to_owned(), Style::new().fg(Color::Indexed(208))),
+                (
+                    "b".to_owned(),
+                    Style::new().fg(Color::Indexed(208)).bg(Color::Indexed(17))
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn rgb_colors_are_parsed_from_parameters_and_subparameters() {
+        assert_eq!(
+            spans("\x1b[38;2;1;2;3ma\x1b[48:2::4:5:6mb\x1b[38:2:7:8:9mc"),
+            vec![
+                ("a".to_owned(), Style::new().fg(Color::Rgb(1, 2, 3))),
+                (
+                    "b".to_owned(),
+                    Style::new().fg(Color::Rgb(1, 2, 3)).bg(Color::Rgb(4, 5, 6))
+                ),
+                (
+                    "c".to_owned(),
+                    Style::new().fg(Color::Rgb(7, 8, 9)).bg(Color::Rgb(4, 5, 6))
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn underline_is_turned_off_by_subparameter_zero() {
+        let spans = spans("\x1b[4:3ma\x1b[4:0mb");
+        assert!(spans[0].1.add_modifier.contains(Modifier::UNDERLINED));
+        assert!(!spans[1].1.add_modifier.contains(Modifier::UNDERLINED));
+    }
+
+    #[test]
+    fn osc_is_terminated_by_bel_and_by_st() {
+        assert_eq!(
+            spans("a\x1b]8;;https://example.com\x07b\x1b]8;;\x1b\\c"),
+            vec![("abc".to_owned(), Style::new())]
+        );
+    }
+}
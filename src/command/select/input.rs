@@ -1,64 +1,265 @@
-use ratatui::crossterm::{
-    self,
-    event::{Event, KeyCode, KeyEventKind, KeyModifiers},
+use ratatui::{
+    crossterm::{
+        self,
+        event::{
+            Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers, MouseButton, MouseEvent,
+            MouseEventKind,
+        },
+    },
+    layout::Position,
 };
 
-use crate::config::Config;
+use crate::{config::Config, keymap::Action};
 
-use super::state::State;
+use super::state::{Mode, PendingAction, State};
 
 pub fn process(config: &Config, state: &mut State) -> anyhow::Result<bool> {
-    let Event::Key(key) = crossterm::event::read()? else {
-        return Ok(false);
+    let is_closed = process_event(config, state, crossterm::event::read()?)?;
+    // Like in vi, the cursor is always on a grapheme in the normal mode.
+    if state.mode() == Mode::Normal {
+        state.cursor_clamp();
+    }
+    Ok(is_closed)
+}
+
+fn process_event(config: &Config, state: &mut State, event: Event) -> anyhow::Result<bool> {
+    let key = match event {
+        Event::Key(key) => key,
+        Event::Mouse(mouse) => return process_mouse(config, state, mouse),
+        Event::Paste(text) => {
+            if state.is_input_pending() {
+                state.pending_input_add_str(&text);
+            } else if !state.is_action_pending() && state.mode() != Mode::Normal {
+                // Like typed characters, pasted text is never inserted in the
+                // normal mode.
+                state.paste(&text)?;
+            }
+            return Ok(false);
+        }
+        _ => return Ok(false),
     };
-    if key.kind != KeyEventKind::Press {
+    if key.kind != KeyEventKind::Press || state.dismiss_error() {
         return Ok(false);
     }
 
-    match (key.modifiers, key.code) {
-        (KeyModifiers::CONTROL, KeyCode::Char('j' | 'n')) | (_, KeyCode::Down) => {
-            if config.session_selector.inverted {
-                state.selection_next()?;
-            } else {
-                state.selection_prev()?;
+    if state.is_input_pending() {
+        match key.code {
+            KeyCode::Char(char) if !key.modifiers.contains(KeyModifiers::CONTROL) => {
+                state.pending_input_add(char);
             }
+            KeyCode::Backspace => state.pending_input_delete(),
+            KeyCode::Enter => return state.answer_pending_action(true),
+            _ => return state.answer_pending_action(false),
         }
-        (KeyModifiers::CONTROL, KeyCode::Char('k' | 'p')) | (_, KeyCode::Up) => {
-            if config.session_selector.inverted {
-                state.selection_prev()?;
-            } else {
-                state.selection_next()?;
+        return Ok(false);
+    }
+    if state.is_action_pending() {
+        return state.answer_pending_action(matches!(key.code, KeyCode::Char('y' | 'Y')));
+    }
+
+    if config.session_selector.vi_mode {
+        match state.mode() {
+            Mode::Insert if key.code == KeyCode::Esc && key.modifiers.is_empty() => {
+                state.set_mode(Mode::Normal);
+                // Like in vi, the cursor moves onto the last inserted character.
+                state.cursor_backward();
+                return Ok(false);
+            }
+            Mode::Insert => (),
+            Mode::Normal => {
+                if let Some(is_closed) = process_normal(config, state, key)? {
+                    return Ok(is_closed);
+                }
             }
         }
-        (KeyModifiers::CONTROL, KeyCode::Char('h')) | (_, KeyCode::Left) => {
-            state.cursor_backward();
-        }
-        (KeyModifiers::CONTROL, KeyCode::Char('l')) | (_, KeyCode::Right) => {
-            state.cursor_forward();
+    }
+
+    let Some(action) = config.session_selector.keymap.get(&key) else {
+        if let KeyCode::Char(char) = key.code
+            && !key
+                .modifiers
+                .intersects(KeyModifiers::CONTROL | KeyModifiers::ALT)
+        {
+            state.char_add(char)?;
         }
-        (_, KeyCode::Tab) => {
-            state.selection_next()?;
+        return Ok(false);
+    };
+
+    if let Action::Function(action_fn) = action {
+        let path_opt = state
+            .selected_path()
+            .map(|path| path.to_string_lossy().to_string());
+        let session_name_opt = state
+            .selected_session()
+            .map(|session| session.name().to_string());
+        let action_str_opt = action_fn.call::<Option<String>>((
+            state.pattern_string(),
+            path_opt,
+            session_name_opt,
+        ))?;
+        return match action_str_opt {
+            Some(action_str) => perform(config, state, &action_str.parse()?),
+            None => Ok(false),
+        };
+    }
+    perform(config, state, action)
+}
+
+/// Processes the key in the normal mode of the vi mode and returns whether the
+/// session selector should be closed, or `None`, if the key is left to the
+/// keymap.
+fn process_normal(
+    config: &Config,
+    state: &mut State,
+    key: KeyEvent,
+) -> anyhow::Result<Option<bool>> {
+    let pending_key_opt = state.take_pending_vi_key();
+    let KeyCode::Char(char) = key.code else {
+        return Ok(None);
+    };
+    if key.modifiers.contains(KeyModifiers::CONTROL) {
+        let half_page = usize::from(state.results_area().height / 2).max(1);
+        match char {
+            'd' => state.selection_move(half_page, is_next_result(config, false))?,
+            'u' => state.selection_move(half_page, is_next_result(config, true))?,
+            _ => return Ok(None),
         }
-        (_, KeyCode::BackTab) => {
-            state.selection_prev()?;
+        return Ok(Some(false));
+    }
+    if key.modifiers.contains(KeyModifiers::ALT) {
+        return Ok(None);
+    }
+
+    match (pending_key_opt, char) {
+        (Some('g'), 'g') => state.selection_edge(is_next_result(config, true))?,
+        (Some('d'), 'd') => state.delete_line()?,
+        (Some('c'), 'w') => {
+            state.word_delete_forward()?;
+            state.set_mode(Mode::Insert);
         }
-        (_, KeyCode::Char(char)) => {
-            state.char_add(char)?;
+        (None, 'g' | 'd' | 'c') => state.set_pending_vi_key(char),
+        (None, 'j') => return perform(config, state, &Action::SelectDown).map(Some),
+        (None, 'k') => return perform(config, state, &Action::SelectUp).map(Some),
+        (None, 'G') => state.selection_edge(is_next_result(config, false))?,
+        (None, 'h') => state.cursor_backward(),
+        (None, 'l') => state.cursor_forward(),
+        (None, 'w') => state.cursor_next_word_start(),
+        (None, 'b') => state.cursor_word_backward(),
+        (None, '0') => state.cursor_start(),
+        (None, '$') => state.cursor_end(),
+        (None, 'x') => state.char_delete_forward()?,
+        (None, 'p') => {
+            state.cursor_forward();
+            state.yank()?;
         }
-        (_, KeyCode::Backspace) => {
-            state.char_delete_backward()?;
+        (None, 'i') => state.set_mode(Mode::Insert),
+        (None, 'a') => {
+            state.cursor_forward();
+            state.set_mode(Mode::Insert);
         }
-        (_, KeyCode::Delete) => {
-            state.char_delete_forward()?;
+        (None, 'I') => {
+            state.cursor_start();
+            state.set_mode(Mode::Insert);
         }
-        (_, KeyCode::Esc) => {
-            return state.abort().map(|()| true);
+        (None, 'A') => {
+            state.cursor_end();
+            state.set_mode(Mode::Insert);
         }
-        (_, KeyCode::Enter) => {
-            return state.confirm();
+        // An unknown second key cancels the command and other characters are
+        // never inserted in the normal mode.
+        _ => (),
+    }
+    Ok(Some(false))
+}
+
+fn process_mouse(config: &Config, state: &mut State, mouse: MouseEvent) -> anyhow::Result<bool> {
+    if state.is_action_pending() {
+        return Ok(false);
+    }
+    let position = Position::new(mouse.column, mouse.row);
+    match mouse.kind {
+        MouseEventKind::Down(MouseButton::Left) => {
+            let results_area = state.results_area();
+            let pattern_area = state.pattern_area();
+            if results_area.contains(position) {
+                let pos = if is_next_result(config, false) {
+                    mouse.row - results_area.top()
+                } else {
+                    results_area.bottom() - 1 - mouse.row
+                };
+                if state.click_result(pos.into())? {
+                    return perform(config, state, &Action::Confirm);
+                }
+            } else if pattern_area.contains(position) {
+                state.click_pattern((mouse.column - pattern_area.left()).into());
+            }
         }
+        MouseEventKind::ScrollUp => return perform(config, state, &Action::SelectUp),
+        MouseEventKind::ScrollDown => return perform(config, state, &Action::SelectDown),
         _ => (),
     }
+    Ok(false)
+}
 
+/// Performs the action and returns whether the session selector should be
+/// closed.
+fn perform(config: &Config, state: &mut State, action: &Action) -> anyhow::Result<bool> {
+    match action {
+        Action::SelectNext => state.selection_next()?,
+        Action::SelectPrev => state.selection_prev()?,
+        Action::SelectUpOrRecallQuery => {
+            if state.is_query_recallable() {
+                state.recall_query()?;
+            } else {
+                return perform(config, state, &Action::SelectUp);
+            }
+        }
+        Action::SelectUp | Action::SelectDown => {
+            if is_next_result(config, matches!(action, Action::SelectUp)) {
+                state.selection_next()?;
+            } else {
+                state.selection_prev()?;
+            }
+        }
+        Action::CursorLeft => state.cursor_backward(),
+        Action::CursorRight => state.cursor_forward(),
+        Action::CursorStart => state.cursor_start(),
+        Action::CursorEnd => state.cursor_end(),
+        Action::CursorWordLeft => state.cursor_word_backward(),
+        Action::CursorWordRight => state.cursor_word_forward(),
+        Action::DeleteBackward => state.char_delete_backward()?,
+        Action::DeleteForward => state.char_delete_forward()?,
+        Action::DeleteWord => state.word_delete_backward()?,
+        Action::DeleteWordForward => state.word_delete_forward()?,
+        Action::DeleteToStart => state.delete_to_start()?,
+        Action::DeleteToEnd => state.delete_to_end()?,
+        Action::Yank => state.yank()?,
+        // Directories cannot be marked, so the path is completed instead.
+        Action::ToggleMark if state.is_browsing() || state.is_path_typed() => {
+            state.complete_path()?;
+        }
+        Action::ToggleMark => state.toggle_mark(true)?,
+        Action::ToggleMarkPrev => state.toggle_mark(false)?,
+        Action::CycleSort => state.cycle_sort_mode()?,
+        Action::CycleMatchingMode => state.cycle_matching_mode()?,
+        Action::RecallQuery => state.recall_query()?,
+        Action::CompletePath => state.complete_path()?,
+        Action::ToggleBrowse => state.toggle_browse()?,
+        Action::Confirm => return state.confirm(),
+        Action::Abort => return state.abort().map(|()| true),
+        Action::KillSession => state.request_action(PendingAction::Kill),
+        Action::DetachOtherClients => state.request_action(PendingAction::DetachOtherClients),
+        Action::SendKeys => state.request_action(PendingAction::SendKeys),
+        // Functions can only return names of actions, which are never
+        // functions themselves.
+        Action::Function(_) => (),
+    }
     Ok(false)
 }
+
+/// Returns whether the result above the selected one on the screen, or below
+/// it otherwise, is the next result, as the results are displayed from bottom
+/// to top, unless inverted.
+fn is_next_result(config: &Config, is_up: bool) -> bool {
+    is_up != config.session_selector.inverted
+}
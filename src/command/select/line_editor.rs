@@ -0,0 +1,430 @@
+use unicode_segmentation::UnicodeSegmentation as _;
+use unicode_width::UnicodeWidthStr as _;
+
+/// A single line editor similar to readline, whose cursor moves by grapheme
+/// clusters and is placed by display width.
+#[derive(Debug, Default)]
+pub struct LineEditor {
+    text: String,
+    /// The byte position of the cursor, which is always at a grapheme
+    /// boundary.
+    cursor_pos: usize,
+    /// The display width of the text, which is scrolled out of view on the
+    /// left.
+    scroll_width: usize,
+    /// The text, which has last been killed and can be yanked.
+    kill_buffer: String,
+}
+
+impl LineEditor {
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.text.is_empty()
+    }
+
+    /// Replaces the text and moves the cursor to its end.
+    pub fn set_text(&mut self, text: &str) {
+        self.text = text.to_string();
+        self.cursor_pos = self.text.len();
+    }
+
+    /// Returns the display width of the text before the cursor.
+    pub fn cursor_width(&self) -> usize {
+        self.text[..self.cursor_pos].width()
+    }
+
+    /// Moves the cursor to the grapheme at the given display width of the
+    /// visible text.
+    pub fn set_cursor_width(&mut self, width: usize) {
+        let start_pos = self.visible_start_pos();
+        let mut grapheme_width_sum = 0;
+        self.cursor_pos = self.text[start_pos..]
+            .grapheme_indices(true)
+            .find(|(_, grapheme)| {
+                grapheme_width_sum += grapheme.width();
+                grapheme_width_sum > width
+            })
+            .map_or(self.text.len(), |(pos, _)| start_pos + pos);
+    }
+
+    /// Scrolls the text horizontally, so the cursor is visible within the
+    /// given display width.
+    pub fn adjust_scroll_width(&mut self, width: usize) {
+        let cursor_width = self.cursor_width();
+        if cursor_width < self.scroll_width {
+            self.scroll_width = cursor_width;
+        } else if cursor_width >= self.scroll_width + width {
+            // The cursor occupies a column itself after the text before it.
+            self.scroll_width = (cursor_width + 1).saturating_sub(width);
+        }
+        // Scroll back, if the text is shorter than before.
+        let text_width = self.text.width();
+        if text_width < self.scroll_width + width {
+            self.scroll_width = (text_width + 1)
+                .saturating_sub(width)
+                .min(self.scroll_width);
+        }
+    }
+
+    /// Returns the visible text, which starts at the first grapheme not
+    /// scrolled out of view, and the display width of the text before the
+    /// cursor within it.
+    pub fn visible_text(&self) -> (&str, usize) {
+        let start_pos = self.visible_start_pos();
+        (
+            &self.text[start_pos..],
+            self.text[start_pos..self.cursor_pos].width(),
+        )
+    }
+
+    pub fn move_left(&mut self) {
+        self.cursor_pos = self.prev_grapheme_pos();
+    }
+
+    pub fn move_right(&mut self) {
+        self.cursor_pos = self.next_grapheme_pos();
+    }
+
+    pub fn move_start(&mut self) {
+        self.cursor_pos = 0;
+    }
+
+    pub fn move_end(&mut self) {
+        self.cursor_pos = self.text.len();
+    }
+
+    /// Moves the cursor onto the last grapheme, if it is behind the text.
+    pub fn clamp_cursor(&mut self) {
+        if self.cursor_pos == self.text.len() {
+            self.move_left();
+        }
+    }
+
+    /// Moves the cursor to the start of the current or previous word.
+    pub fn move_word_left(&mut self) {
+        self.cursor_pos = self.prev_word_pos(is_word_char);
+    }
+
+    /// Moves the cursor to the end of the current or next word.
+    pub fn move_word_right(&mut self) {
+        self.cursor_pos = self.next_word_pos();
+    }
+
+    /// Moves the cursor to the start of the next word like `w` in vi, where
+    /// punctuation forms words of its own.
+    pub fn move_next_word_start(&mut self) {
+        self.cursor_pos = self.next_word_start_pos();
+    }
+
+    /// Inserts the text at the cursor and moves the cursor behind it.
+    pub fn insert(&mut self, text: &str) {
+        self.text.insert_str(self.cursor_pos, text);
+        self.cursor_pos += text.len();
+    }
+
+    /// Deletes the grapheme before the cursor and returns whether the text has
+    /// changed.
+    pub fn delete_backward(&mut self) -> bool {
+        let pos = self.prev_grapheme_pos();
+        self.delete(pos, self.cursor_pos, false)
+    }
+
+    /// Deletes the grapheme after the cursor and returns whether the text has
+    /// changed.
+    pub fn delete_forward(&mut self) -> bool {
+        let pos = self.next_grapheme_pos();
+        self.delete(self.cursor_pos, pos, false)
+    }
+
+    /// Kills the whitespace delimited word before the cursor including
+    /// trailing whitespace and returns whether the text has changed.
+    pub fn kill_word_backward(&mut self) -> bool {
+        let pos = self.prev_word_pos(|grapheme| !grapheme.trim().is_empty());
+        self.delete(pos, self.cursor_pos, true)
+    }
+
+    /// Kills the text up to the end of the current or next word and returns
+    /// whether the text has changed.
+    pub fn kill_word_forward(&mut self) -> bool {
+        let pos = self.next_word_pos();
+        self.delete(self.cursor_pos, pos, true)
+    }
+
+    /// Kills the text before the cursor and returns whether the text has
+    /// changed.
+    pub fn kill_to_start(&mut self) -> bool {
+        self.delete(0, self.cursor_pos, true)
+    }
+
+    /// Kills the text after the cursor and returns whether the text has
+    /// changed.
+    pub fn kill_to_end(&mut self) -> bool {
+        self.delete(self.cursor_pos, self.text.len(), true)
+    }
+
+    /// Inserts the last killed text at the cursor and returns whether the text
+    /// has changed.
+    pub fn yank(&mut self) -> bool {
+        if self.kill_buffer.is_empty() {
+            return false;
+        }
+        self.text.insert_str(self.cursor_pos, &self.kill_buffer);
+        self.cursor_pos += self.kill_buffer.len();
+        true
+    }
+
+    fn delete(&mut self, start_pos: usize, end_pos: usize, is_kill: bool) -> bool {
+        if start_pos == end_pos {
+            return false;
+        }
+        let text = self.text.drain(start_pos..end_pos).collect::<String>();
+        if is_kill {
+            self.kill_buffer = text;
+        }
+        self.cursor_pos = start_pos;
+        true
+    }
+
+    fn visible_start_pos(&self) -> usize {
+        let mut grapheme_width_sum = 0;
+        self.text
+            .grapheme_indices(true)
+            .find(|(_, grapheme)| {
+                let is_visible = grapheme_width_sum >= self.scroll_width;
+                grapheme_width_sum += grapheme.width();
+                is_visible
+            })
+            .map_or(self.text.len(), |(pos, _)| pos)
+            .min(self.cursor_pos)
+    }
+
+    fn prev_grapheme_pos(&self) -> usize {
+        self.text[..self.cursor_pos]
+            .grapheme_indices(true)
+            .next_back()
+            .map_or(0, |(pos, _)| pos)
+    }
+
+    fn next_grapheme_pos(&self) -> usize {
+        self.text[self.cursor_pos..]
+            .graphemes(true)
+            .next()
+            .map_or(self.cursor_pos, |grapheme| self.cursor_pos + grapheme.len())
+    }
+
+    /// Returns the start of the current or previous word, which consists of
+    /// the graphemes matching the predicate.
+    fn prev_word_pos(&self, is_word_grapheme_fn: impl Fn(&str) -> bool) -> usize {
+        self.text[..self.cursor_pos]
+            .grapheme_indices(true)
+            .rev()
+            .skip_while(|(_, grapheme)| !is_word_grapheme_fn(grapheme))
+            .take_while(|(_, grapheme)| is_word_grapheme_fn(grapheme))
+            .last()
+            .map_or(0, |(pos, _)| pos)
+    }
+
+    /// Returns the end of the current or next word.
+    fn next_word_pos(&self) -> usize {
+        self.text[self.cursor_pos..]
+            .grapheme_indices(true)
+            .skip_while(|(_, grapheme)| !is_word_char(grapheme))
+            .find(|(_, grapheme)| !is_word_char(grapheme))
+            .map_or(self.text.len(), |(pos, _)| self.cursor_pos + pos)
+    }
+
+    /// Returns the start of the next word, which is either a run of word
+    /// graphemes or a run of punctuation.
+    fn next_word_start_pos(&self) -> usize {
+        let mut graphemes = self.text[self.cursor_pos..].grapheme_indices(true);
+        let Some((_, grapheme)) = graphemes.next() else {
+            return self.cursor_pos;
+        };
+        let class = GraphemeClass::of(grapheme);
+        graphemes
+            .skip_while(|(_, grapheme)| {
+                class != GraphemeClass::Whitespace && GraphemeClass::of(grapheme) == class
+            })
+            .find(|(_, grapheme)| GraphemeClass::of(grapheme) != GraphemeClass::Whitespace)
+            .map_or(self.text.len(), |(pos, _)| self.cursor_pos + pos)
+    }
+}
+
+/// The class of a grapheme, whose boundaries are the word starts of vi.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum GraphemeClass {
+    Whitespace,
+    Word,
+    Punctuation,
+}
+
+impl GraphemeClass {
+    fn of(grapheme: &str) -> Self {
+        if grapheme.trim().is_empty() {
+            Self::Whitespace
+        } else if is_word_char(grapheme) {
+            Self::Word
+        } else {
+            Self::Punctuation
+        }
+    }
+}
+
+/// Returns whether the grapheme is part of a word for word motions, which stop
+/// at punctuation like path separators.
+fn is_word_char(grapheme: &str) -> bool {
+    grapheme.chars().any(char::is_alphanumeric)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn line_editor(text: &str) -> LineEditor {
+        let mut line_editor = LineEditor::default();
+        line_editor.set_text(text);
+        line_editor
+    }
+
+    #[test]
+    fn word_motions_stop_at_punctuation() {
+        let mut line_editor = line_editor("~/foo/bar baz");
+        line_editor.move_word_left();
+        assert_eq!(line_editor.cursor_pos, 10);
+        line_editor.move_word_left();
+        assert_eq!(line_editor.cursor_pos, 6);
+        line_editor.move_word_left();
+        assert_eq!(line_editor.cursor_pos, 2);
+        line_editor.move_word_left();
+        assert_eq!(line_editor.cursor_pos, 0);
+        line_editor.move_word_right();
+        assert_eq!(line_editor.cursor_pos, 5);
+        line_editor.move_word_right();
+        assert_eq!(line_editor.cursor_pos, 9);
+        line_editor.move_word_right();
+        assert_eq!(line_editor.cursor_pos, 13);
+        line_editor.move_word_right();
+        assert_eq!(line_editor.cursor_pos, 13);
+    }
+
+    #[test]
+    fn next_word_start_skips_whitespace() {
+        let mut line_editor = line_editor("foo bar");
+        line_editor.move_start();
+        line_editor.move_next_word_start();
+        assert_eq!(line_editor.cursor_pos, 4);
+        line_editor.move_next_word_start();
+        assert_eq!(line_editor.cursor_pos, 7);
+        line_editor.move_next_word_start();
+        assert_eq!(line_editor.cursor_pos, 7);
+    }
+
+    #[test]
+    fn next_word_start_stops_at_punctuation() {
+        let mut line_editor = line_editor("~/foo  bar");
+        line_editor.move_start();
+        line_editor.move_next_word_start();
+        assert_eq!(line_editor.cursor_pos, 2);
+        line_editor.move_next_word_start();
+        assert_eq!(line_editor.cursor_pos, 7);
+    }
+
+    #[test]
+    fn kill_word_backward_is_delimited_by_whitespace() {
+        let mut line_editor = line_editor("foo/bar baz ");
+        assert!(line_editor.kill_word_backward());
+        assert_eq!(line_editor.text(), "foo/bar ");
+        assert!(line_editor.kill_word_backward());
+        assert_eq!(line_editor.text(), "");
+        assert!(!line_editor.kill_word_backward());
+        assert!(line_editor.yank());
+        assert_eq!(line_editor.text(), "foo/bar ");
+    }
+
+    #[test]
+    fn kill_word_forward_kills_to_the_end_of_the_word() {
+        let mut line_editor = line_editor("foo/bar");
+        line_editor.move_start();
+        assert!(line_editor.kill_word_forward());
+        assert_eq!(line_editor.text(), "/bar");
+        assert!(line_editor.kill_word_forward());
+        assert_eq!(line_editor.text(), "");
+    }
+
+    #[test]
+    fn cursor_moves_by_graphemes() {
+        let mut line_editor = line_editor("e\u{301}日");
+        line_editor.move_left();
+        assert_eq!(line_editor.cursor_pos, 3);
+        assert_eq!(line_editor.cursor_width(), 1);
+        line_editor.move_left();
+        assert_eq!(line_editor.cursor_pos, 0);
+        line_editor.move_right();
+        line_editor.move_right();
+        assert_eq!(line_editor.cursor_width(), 3);
+        assert!(line_editor.delete_backward());
+        assert_eq!(line_editor.text(), "e\u{301}");
+    }
+
+    #[test]
+    fn clamp_cursor_moves_onto_last_grapheme() {
+        let mut line_editor = line_editor("ab日");
+        line_editor.clamp_cursor();
+        assert_eq!(line_editor.cursor_pos, 2);
+        line_editor.clamp_cursor();
+        assert_eq!(line_editor.cursor_pos, 2);
+        let mut line_editor = LineEditor::default();
+        line_editor.clamp_cursor();
+        assert_eq!(line_editor.cursor_pos, 0);
+    }
+
+    #[test]
+    fn cursor_is_placed_by_display_width() {
+        let mut line_editor = line_editor("日本語");
+        assert_eq!(line_editor.cursor_width(), 6);
+        // A column within a wide grapheme places the cursor before it.
+        line_editor.set_cursor_width(3);
+        assert_eq!(line_editor.cursor_pos, 3);
+        line_editor.set_cursor_width(4);
+        assert_eq!(line_editor.cursor_pos, 6);
+        line_editor.set_cursor_width(10);
+        assert_eq!(line_editor.cursor_pos, 9);
+    }
+
+    #[test]
+    fn adjust_scroll_width_keeps_cursor_visible() {
+        let mut line_editor = line_editor("abcdefghij");
+        line_editor.adjust_scroll_width(5);
+        assert_eq!(line_editor.scroll_width, 6);
+        assert_eq!(line_editor.visible_text(), ("ghij", 4));
+        line_editor.move_start();
+        line_editor.adjust_scroll_width(5);
+        assert_eq!(line_editor.scroll_width, 0);
+        assert_eq!(line_editor.visible_text(), ("abcdefghij", 0));
+    }
+
+    #[test]
+    fn adjust_scroll_width_scrolls_back_for_shorter_text() {
+        let mut line_editor = line_editor("abcdefghij");
+        line_editor.adjust_scroll_width(5);
+        line_editor.set_text("abcdefg");
+        line_editor.adjust_scroll_width(5);
+        assert_eq!(line_editor.scroll_width, 3);
+        assert_eq!(line_editor.visible_text(), ("defg", 4));
+        line_editor.set_text("abc");
+        line_editor.adjust_scroll_width(5);
+        assert_eq!(line_editor.scroll_width, 0);
+        assert_eq!(line_editor.visible_text(), ("abc", 3));
+    }
+
+    #[test]
+    fn adjust_scroll_width_skips_partially_visible_wide_graphemes() {
+        let mut line_editor = line_editor("日本語日本語");
+        line_editor.adjust_scroll_width(6);
+        assert_eq!(line_editor.scroll_width, 7);
+        assert_eq!(line_editor.visible_text(), ("本語", 4));
+    }
+}
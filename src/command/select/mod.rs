@@ -1,53 +1,142 @@
+mod ansi;
 mod input;
+mod line_editor;
 mod state;
 mod ui;
 
+use std::{io::Write, time::Duration};
+
 use ratatui::{
     crossterm::{
         self,
         cursor::SetCursorStyle,
+        event::{
+            DisableBracketedPaste, DisableMouseCapture, EnableBracketedPaste, EnableMouseCapture,
+        },
         terminal::{EnterAlternateScreen, LeaveAlternateScreen},
     },
     prelude::*,
 };
 
-use crate::{args::Args, config::Config, tmux};
+use crate::{
+    args::{self, Args},
+    command,
+    config::Config,
+    tmux,
+};
+
+use self::state::{Mode, State};
 
-use self::state::State;
+pub fn select(args: &Args, args_select: &args::Select, config: &Config) -> anyhow::Result<()> {
+    // Outside of tmux the session selector runs in the terminal itself, which
+    // is attached to the confirmed session afterwards.
+    let exit_empty_opt = if tmux::is_in_session() {
+        None
+    } else {
+        Some(tmux::start_server()?)
+    };
+
+    let res = open(args, args_select, config);
+    // Without any sessions the server exits again, while creating the
+    // project's session starts a new one. The option is restored before
+    // checking for errors, so the server does not keep running forever.
+    if let Some(exit_empty) = &exit_empty_opt {
+        tmux::restore_exit_empty(exit_empty)?;
+    }
+    let state = res?;
+
+    if let Some(project) = state.project_to_create() {
+        command::create_sessions(args, std::slice::from_ref(project), false, config)?;
+    }
+    if let Some(session) = state.session_to_switch_to() {
+        session.switch_to(args.target_client.as_ref())?;
+    }
+    if args_select.temporary_pane {
+        tmux::kill_current_pane()?;
+    }
 
-pub fn select(args: &Args, config: &Config) -> anyhow::Result<()> {
-    tmux::assert_in_session()?;
+    Ok(())
+}
 
-    let mut state = State::new(args, config)?;
+/// Opens the session selector in the terminal until it is closed and returns
+/// its final state.
+fn open<'a>(
+    args: &'a Args,
+    args_select: &args::Select,
+    config: &'a Config,
+) -> anyhow::Result<State<'a>> {
+    // Switching to another session would hide a temporary pane, so the
+    // selected session is only switched to on exit.
+    let is_switch_deferred = !tmux::is_in_session() || args_select.temporary_pane;
+    // A window created for the session selector is closed on exit, so it is
+    // not counted as a window of its session.
+    let is_window_temporary = args_select.temporary_pane && tmux::is_current_pane_alone()?;
+    let mut state = State::new(args, config, is_switch_deferred, is_window_temporary)?;
     let mut terminal = Terminal::new(CrosstermBackend::new(std::io::stdout()))?;
 
     crossterm::terminal::enable_raw_mode()?;
     crossterm::execute!(
         terminal.backend_mut(),
         EnterAlternateScreen,
-        SetCursorStyle::SteadyBar,
+        EnableBracketedPaste,
+        cursor_style(config, state.mode()),
     )?;
+    if config.session_selector.mouse {
+        crossterm::execute!(terminal.backend_mut(), EnableMouseCapture)?;
+    }
 
     let res = run(config, &mut state, &mut terminal);
 
     crossterm::terminal::disable_raw_mode()?;
+    if config.session_selector.mouse {
+        crossterm::execute!(terminal.backend_mut(), DisableMouseCapture)?;
+    }
     crossterm::execute!(
         terminal.backend_mut(),
         LeaveAlternateScreen,
+        DisableBracketedPaste,
         SetCursorStyle::DefaultUserShape,
     )?;
 
-    res
+    res?;
+    Ok(state)
 }
 
 fn run<B>(config: &Config, state: &mut State, terminal: &mut Terminal<B>) -> anyhow::Result<()>
 where
-    B: Backend,
+    B: Backend + Write,
 {
+    let live_switch_delay = Duration::from_millis(config.session_selector.live_switch_delay);
+    let preview_refresh_interval =
+        Duration::from_millis(config.session_selector.preview.refresh_interval.get());
+    let mut mode = state.mode();
     loop {
+        if state.mode() != mode {
+            mode = state.mode();
+            crossterm::execute!(terminal.backend_mut(), cursor_style(config, mode))?;
+        }
         terminal.try_draw(|frame| ui::draw(config, state, frame))?;
+        if state.is_switch_pending() && !crossterm::event::poll(live_switch_delay)? {
+            state.switch_pending_session()?;
+            continue;
+        }
+        // Redraw the preview periodically, while there is no input.
+        if config.session_selector.preview.enabled
+            && !crossterm::event::poll(preview_refresh_interval)?
+        {
+            continue;
+        }
         if input::process(config, state)? {
             return Ok(());
         }
     }
 }
+
+fn cursor_style(config: &Config, mode: Mode) -> SetCursorStyle {
+    let cursor_style = &config.session_selector.cursor_style;
+    match mode {
+        Mode::Insert => cursor_style.insert,
+        Mode::Normal => cursor_style.normal,
+    }
+    .into()
+}
@@ -1,78 +1,319 @@
-use crate::{args::Args, config::Config, tmux::Session};
+use std::{
+    cmp::Reverse,
+    path::{Component, Path, PathBuf},
+    time::{Duration, Instant},
+};
+
+use ahash::HashSet;
+use ratatui::layout::Rect;
+
+use crate::{
+    args::Args,
+    command,
+    config::{Config, LiveSwitch, MatchingMode, ResultsColumn, SortMode},
+    discovery,
+    frecency::{self, Frecency},
+    git, matching, path, query_history,
+    query_history::QueryHistory,
+    tmux::{self, History, Session},
+};
+
+use super::line_editor::LineEditor;
+
+/// The maximum interval between two clicks on the same result, so they are
+/// considered a double-click.
+const DOUBLE_CLICK_INTERVAL: Duration = Duration::from_millis(400);
+
+/// An item of the results, which is either an existing session or a
+/// discovered project directory without a session.
+pub enum Item<'b> {
+    Session(&'b Session),
+    Project(&'b PathBuf),
+    /// A new session for the path, which has been typed as pattern.
+    NewSession(&'b PathBuf),
+    /// A subdirectory of the path, which has been typed as pattern.
+    Directory(&'b PathBuf),
+}
+
+/// An action, which has to be confirmed first.
+#[derive(Clone, Copy)]
+pub enum PendingAction {
+    /// Kills the marked sessions or the selected session.
+    Kill,
+    /// Detaches the other clients from the marked sessions or the selected
+    /// session.
+    DetachOtherClients,
+    /// Sends the entered command to the marked sessions or the selected
+    /// session.
+    SendKeys,
+    /// Creates the missing directory of the new session.
+    CreateDirs,
+}
+
+/// The editing mode of the prompt, which is always the insert mode without the
+/// vi mode.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    Insert,
+    /// Keys navigate the results and edit the pattern like in vi.
+    Normal,
+}
 
 pub struct State<'a> {
     args: &'a Args,
+    config: &'a Config,
     initial_session_opt: Option<Session>,
     sessions: Vec<Session>,
-    session_paths: Vec<String>,
-    pattern: Vec<char>,
+    projects: Vec<PathBuf>,
+    item_paths: Vec<String>,
+    item_frecency_bonuses: Vec<f64>,
+    item_git_branches: Vec<Option<String>>,
+    pattern: LineEditor,
     matches: Vec<(usize, Vec<usize>)>,
-    cursor_pos: usize,
     scroll_pos: usize,
     selection_pos: usize,
+    project_to_create_opt: Option<PathBuf>,
+    /// Whether the selected session is only switched to or attached after
+    /// the session selector has been closed.
+    is_switch_deferred: bool,
+    session_to_switch_to_opt: Option<Session>,
+    marked_session_ids: HashSet<String>,
+    pending_action_opt: Option<PendingAction>,
+    /// The message of an error, which is displayed in place of the pattern
+    /// until the next key press instead of closing the session selector.
+    error_opt: Option<String>,
+    pending_input: String,
+    sort_mode: SortMode,
+    matching_mode: MatchingMode,
+    new_session_path_opt: Option<PathBuf>,
+    new_session_label: String,
+    directories: Vec<PathBuf>,
+    directory_names: Vec<String>,
+    is_switch_pending: bool,
+    /// Whether the subdirectories of the pattern as a path are browsed,
+    /// regardless of the pattern.
+    is_browse_mode: bool,
+    results_area: Rect,
+    pattern_area: Rect,
+    last_click_opt: Option<(Instant, usize)>,
+    /// The id of the session, whose active pane has been captured for the
+    /// preview, the time of the capture and the captured contents.
+    preview_opt: Option<(String, Instant, String)>,
+    queries: Vec<String>,
+    query_pos_opt: Option<usize>,
+    mode: Mode,
+    /// The first key of a vi command consisting of two keys like `dd`.
+    pending_vi_key_opt: Option<char>,
 }
 
 impl<'a> State<'a> {
-    pub fn new(args: &'a Args, config: &Config) -> anyhow::Result<Self> {
-        let initial_session_opt = Session::current(args.target_client.as_ref())?;
-        let sessions = Session::all()?;
-        let sessions_map_fn = |session: &Session| {
-            let mut path = String::new();
-            match session
-                .path()
-                .strip_prefix(dirs::home_dir().unwrap_or_default())
-            {
-                Ok(path_stripped) if config.session_selector.paths.truncate_home_dir => {
-                    path.push_str(&config.session_selector.paths.home_dir_symbol);
-                    path.push('/');
-                    path.push_str(&path_stripped.to_string_lossy());
-                }
-                _ => path.push_str(&session.path().to_string_lossy()),
-            }
-            if config.session_selector.paths.trailing_slash {
-                path.push('/');
-            }
-            path
-        };
-        let session_paths = sessions.iter().map(sessions_map_fn).collect::<Vec<_>>();
-        let matches = session_paths
+    pub fn new(
+        args: &'a Args,
+        config: &'a Config,
+        is_switch_deferred: bool,
+        is_window_temporary: bool,
+    ) -> anyhow::Result<Self> {
+        let initial_session_opt = tmux::is_in_session()
+            .then(|| Session::current(args.target_client.as_ref()))
+            .transpose()?
+            .flatten();
+        let mut sessions = Session::all()?;
+        if is_window_temporary
+            && let Some(initial_session) = &initial_session_opt
+            && let Some(session) = sessions
+                .iter_mut()
+                .find(|session| session.id() == initial_session.id())
+        {
+            session.exclude_window();
+        }
+        let session_paths = sessions
             .iter()
-            .enumerate()
-            .map(|(i, _)| (i, Vec::new()))
-            .collect();
-        Ok(Self {
+            .filter_map(|session| session.path().canonicalize().ok())
+            .collect::<HashSet<_>>();
+        let projects = discovery::discover(&config.session_selector.discovery)
+            .into_iter()
+            .filter(|project| !session_paths.contains(project))
+            .collect::<Vec<_>>();
+        let mut state = Self::with_items(
+            args,
+            config,
+            is_switch_deferred,
+            initial_session_opt,
+            sessions,
+            projects,
+            &Frecency::read()?,
+        );
+        state.queries = QueryHistory::read()?.queries().to_vec();
+        if config.session_selector.restore_last_query
+            && let Some(query) = state.queries.last()
+        {
+            state.pattern.set_text(query);
+            // The restored query only prefills the results, so the selected
+            // session is not switched to before any input.
+            let is_switch_deferred = std::mem::replace(&mut state.is_switch_deferred, true);
+            state.match_sessions()?;
+            state.is_switch_deferred = is_switch_deferred;
+        }
+        Ok(state)
+    }
+
+    /// Creates the state for the given sessions and projects, which are
+    /// initially sorted, without any query history.
+    fn with_items(
+        args: &'a Args,
+        config: &'a Config,
+        is_switch_deferred: bool,
+        initial_session_opt: Option<Session>,
+        sessions: Vec<Session>,
+        projects: Vec<PathBuf>,
+        frecency: &Frecency,
+    ) -> Self {
+        let item_paths = sessions
+            .iter()
+            .map(Session::path)
+            .chain(&projects)
+            .map(|path| path::display(path, &config.session_selector.paths))
+            .collect::<Vec<_>>();
+        let item_frecency_bonuses = sessions
+            .iter()
+            .map(Session::path)
+            .chain(&projects)
+            .map(|path| config.session_selector.frecency_weight * frecency.score(path).ln_1p())
+            .collect::<Vec<_>>();
+        // Git branches are only looked up, if they may be displayed.
+        let item_git_branches = if config.session_selector.results.item_format.is_some()
+            || config
+                .session_selector
+                .results
+                .columns
+                .contains(&ResultsColumn::GitBranch)
+        {
+            sessions
+                .iter()
+                .map(Session::path)
+                .chain(&projects)
+                .map(|path| git::branch(path))
+                .collect()
+        } else {
+            Vec::new()
+        };
+        let mut state = Self {
             args,
+            config,
             initial_session_opt,
             sessions,
-            session_paths,
-            pattern: Vec::new(),
-            matches,
-            cursor_pos: 0,
+            projects,
+            item_paths,
+            item_frecency_bonuses,
+            item_git_branches,
+            pattern: LineEditor::default(),
+            matches: Vec::new(),
             scroll_pos: 0,
             selection_pos: 0,
-        })
+            project_to_create_opt: None,
+            is_switch_deferred,
+            session_to_switch_to_opt: None,
+            marked_session_ids: HashSet::default(),
+            pending_action_opt: None,
+            error_opt: None,
+            pending_input: String::new(),
+            sort_mode: config.session_selector.sort,
+            matching_mode: config.session_selector.matching.mode,
+            new_session_path_opt: None,
+            new_session_label: String::new(),
+            directories: Vec::new(),
+            directory_names: Vec::new(),
+            is_switch_pending: false,
+            is_browse_mode: false,
+            results_area: Rect::default(),
+            pattern_area: Rect::default(),
+            last_click_opt: None,
+            preview_opt: None,
+            queries: Vec::new(),
+            query_pos_opt: None,
+            mode: Mode::Insert,
+            pending_vi_key_opt: None,
+        };
+        state.matches = state.sorted_items();
+        state
+    }
+
+    pub fn items_len(&self) -> usize {
+        self.item_paths.len()
+    }
+
+    pub fn get_item_path_by_index(&self, i: usize) -> Option<&String> {
+        if i == self.items_len() && self.new_session_path_opt.is_some() {
+            return Some(&self.new_session_label);
+        }
+        if i > self.items_len() {
+            return self.directory_names.get(i - self.items_len() - 1);
+        }
+        self.item_paths.get(i)
     }
 
-    pub fn sessions_len(&self) -> usize {
-        self.sessions.len()
+    pub fn get_session_by_index(&self, i: usize) -> Option<&Session> {
+        self.sessions.get(i)
     }
 
-    pub fn get_session_path_by_index(&self, i: usize) -> Option<&String> {
-        self.session_paths.get(i)
+    pub fn get_git_branch_by_index(&self, i: usize) -> Option<&String> {
+        self.item_git_branches.get(i).and_then(Option::as_ref)
+    }
+
+    /// Returns whether the item is a discovered project, which excludes the
+    /// new session and the browsed directories following the items.
+    pub fn is_project_by_index(&self, i: usize) -> bool {
+        (self.sessions.len()..self.items_len()).contains(&i)
+    }
+
+    /// Returns the confirmed session, which should be switched to or attached
+    /// after the session selector has been closed.
+    pub fn session_to_switch_to(&self) -> Option<&Session> {
+        self.session_to_switch_to_opt.as_ref()
+    }
+
+    /// Returns the path of the selected project, which should be created after
+    /// the session selector has been closed.
+    pub fn project_to_create(&self) -> Option<&PathBuf> {
+        self.project_to_create_opt.as_ref()
     }
 
     pub fn pattern_string(&self) -> String {
-        self.pattern.iter().collect()
+        self.pattern.text().to_string()
+    }
+
+    /// Returns the visible part of the pattern and the display width of the
+    /// text before the cursor within it.
+    pub fn visible_pattern(&self) -> (&str, usize) {
+        self.pattern.visible_text()
     }
 
-    pub fn cursor_pos(&self) -> usize {
-        self.cursor_pos
+    /// Scrolls the pattern horizontally, so the cursor is visible within the
+    /// given display width.
+    pub fn adjust_pattern_scroll(&mut self, width: usize) {
+        self.pattern.adjust_scroll_width(width);
+    }
+
+    /// Returns the number of items, which are matched against the pattern,
+    /// i.e. the subdirectories of it while it is a path.
+    pub fn candidates_len(&self) -> usize {
+        if self.is_browsing() {
+            // The path itself is always offered in addition to its
+            // subdirectories.
+            self.directories.len() + 1
+        } else {
+            self.items_len()
+        }
     }
 
     pub fn matches_len(&self) -> usize {
         self.matches.len()
     }
 
+    pub fn matches(&self) -> &[(usize, Vec<usize>)] {
+        &self.matches
+    }
+
     pub fn visible_matches(&self, count: usize) -> &[(usize, Vec<usize>)] {
         let end = (self.scroll_pos + count).min(self.matches.len());
         &self.matches[self.scroll_pos..end]
@@ -90,114 +331,846 @@ impl<'a> State<'a> {
         } else if self.selection_pos >= self.matches.len() - scrolloff {
             self.scroll_pos = self.matches.len() - item_count;
         } else if self.selection_pos < self.scroll_pos + scrolloff {
-            self.scroll_pos -= 1;
+            // The selection may have moved by more than one result at once.
+            self.scroll_pos = self.selection_pos - scrolloff;
         } else if self.selection_pos >= self.scroll_pos + item_count - scrolloff {
-            self.scroll_pos += 1;
+            self.scroll_pos = self.selection_pos + scrolloff + 1 - item_count;
         }
     }
 
     pub fn cursor_backward(&mut self) {
-        if self.cursor_pos > 0 {
-            self.cursor_pos -= 1;
-        }
+        self.pattern.move_left();
     }
 
     pub fn cursor_forward(&mut self) {
-        if self.cursor_pos < self.pattern.len() {
-            self.cursor_pos += 1;
-        }
+        self.pattern.move_right();
+    }
+
+    pub fn cursor_start(&mut self) {
+        self.pattern.move_start();
+    }
+
+    pub fn cursor_end(&mut self) {
+        self.pattern.move_end();
+    }
+
+    /// Moves the cursor onto the last grapheme, if it is behind the pattern.
+    pub fn cursor_clamp(&mut self) {
+        self.pattern.clamp_cursor();
+    }
+
+    pub fn cursor_word_backward(&mut self) {
+        self.pattern.move_word_left();
+    }
+
+    pub fn cursor_word_forward(&mut self) {
+        self.pattern.move_word_right();
+    }
+
+    pub fn cursor_next_word_start(&mut self) {
+        self.pattern.move_next_word_start();
     }
 
     pub fn char_add(&mut self, char: char) -> anyhow::Result<()> {
-        if self.cursor_pos > self.pattern.len() {
-            self.pattern.push(char);
-        } else {
-            self.pattern.insert(self.cursor_pos, char);
-        }
-        self.cursor_pos += 1;
+        self.pattern.insert(char.encode_utf8(&mut [0; 4]));
+        self.match_sessions()
+    }
+
+    /// Inserts the pasted text, whose line breaks and other control characters
+    /// are replaced by spaces.
+    pub fn paste(&mut self, text: &str) -> anyhow::Result<()> {
+        let text = text
+            .trim_end_matches(['\r', '\n'])
+            .replace(char::is_control, " ");
+        self.pattern.insert(&text);
         self.match_sessions()
     }
 
     pub fn char_delete_backward(&mut self) -> anyhow::Result<()> {
-        if self.cursor_pos > 0 {
-            self.pattern.remove(self.cursor_pos - 1);
-            self.cursor_pos -= 1;
+        if self.pattern.delete_backward() {
             self.match_sessions()?;
         }
         Ok(())
     }
 
     pub fn char_delete_forward(&mut self) -> anyhow::Result<()> {
-        if self.cursor_pos < self.pattern.len() {
-            self.pattern.remove(self.cursor_pos);
+        if self.pattern.delete_forward() {
+            self.match_sessions()?;
+        }
+        Ok(())
+    }
+
+    /// Deletes the word before the cursor including trailing whitespace.
+    pub fn word_delete_backward(&mut self) -> anyhow::Result<()> {
+        if self.pattern.kill_word_backward() {
+            self.match_sessions()?;
+        }
+        Ok(())
+    }
+
+    pub fn word_delete_forward(&mut self) -> anyhow::Result<()> {
+        if self.pattern.kill_word_forward() {
             self.match_sessions()?;
         }
         Ok(())
     }
 
+    pub fn delete_to_start(&mut self) -> anyhow::Result<()> {
+        if self.pattern.kill_to_start() {
+            self.match_sessions()?;
+        }
+        Ok(())
+    }
+
+    pub fn delete_to_end(&mut self) -> anyhow::Result<()> {
+        if self.pattern.kill_to_end() {
+            self.match_sessions()?;
+        }
+        Ok(())
+    }
+
+    /// Inserts the last deleted word or line part at the cursor.
+    pub fn yank(&mut self) -> anyhow::Result<()> {
+        if self.pattern.yank() {
+            self.match_sessions()?;
+        }
+        Ok(())
+    }
+
+    /// Deletes the whole pattern.
+    pub fn delete_line(&mut self) -> anyhow::Result<()> {
+        self.pattern.move_start();
+        self.delete_to_end()
+    }
+
+    pub fn mode(&self) -> Mode {
+        self.mode
+    }
+
+    pub fn set_mode(&mut self, mode: Mode) {
+        self.mode = mode;
+        self.pending_vi_key_opt = None;
+    }
+
+    /// Returns and clears the first key of a pending vi command.
+    pub fn take_pending_vi_key(&mut self) -> Option<char> {
+        self.pending_vi_key_opt.take()
+    }
+
+    pub fn set_pending_vi_key(&mut self, key: char) {
+        self.pending_vi_key_opt = Some(key);
+    }
+
+    /// Selects the first or, if `is_last`, the last result.
+    pub fn selection_edge(&mut self, is_last: bool) -> anyhow::Result<()> {
+        if self.matches.is_empty() {
+            return Ok(());
+        }
+        self.selection_pos = if is_last { self.matches.len() - 1 } else { 0 };
+        self.switch_session(false)
+    }
+
+    /// Moves the selection by the given number of results towards the next or
+    /// previous ones without wrapping around.
+    pub fn selection_move(&mut self, count: usize, is_next: bool) -> anyhow::Result<()> {
+        if self.matches.is_empty() {
+            return Ok(());
+        }
+        self.selection_pos = if is_next {
+            (self.selection_pos + count).min(self.matches.len() - 1)
+        } else {
+            self.selection_pos.saturating_sub(count)
+        };
+        self.switch_session(false)
+    }
+
     pub fn selection_prev(&mut self) -> anyhow::Result<()> {
+        if self.matches.is_empty() {
+            return Ok(());
+        }
         let matcher_results_len = self.matches.len();
         self.selection_pos = (self.selection_pos + matcher_results_len - 1) % matcher_results_len;
         self.switch_session(false)
     }
 
     pub fn selection_next(&mut self) -> anyhow::Result<()> {
+        if self.matches.is_empty() {
+            return Ok(());
+        }
         self.selection_pos = (self.selection_pos + 1) % self.matches.len();
         self.switch_session(false)
     }
 
+    pub fn selected_path(&self) -> Option<&PathBuf> {
+        match self.get_selected_item() {
+            Ok(Some(Item::Session(session))) => Some(session.path()),
+            Ok(Some(Item::Project(path) | Item::NewSession(path) | Item::Directory(path))) => {
+                Some(path)
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns the area of the terminal, which the visible results have last
+    /// been drawn in.
+    pub fn results_area(&self) -> Rect {
+        self.results_area
+    }
+
+    pub fn set_results_area(&mut self, area: Rect) {
+        self.results_area = area;
+    }
+
+    /// Returns the area of the terminal, which the pattern has last been drawn
+    /// in.
+    pub fn pattern_area(&self) -> Rect {
+        self.pattern_area
+    }
+
+    pub fn set_pattern_area(&mut self, area: Rect) {
+        self.pattern_area = area;
+    }
+
+    /// Selects the visible result at the given position and returns whether
+    /// it has been double-clicked.
+    pub fn click_result(&mut self, pos: usize) -> anyhow::Result<bool> {
+        let selection_pos = self.scroll_pos + pos;
+        if selection_pos >= self.matches.len() {
+            return Ok(false);
+        }
+        let is_double_click = self.last_click_opt.is_some_and(|(instant, last_pos)| {
+            last_pos == selection_pos && instant.elapsed() < DOUBLE_CLICK_INTERVAL
+        });
+        if is_double_click {
+            self.last_click_opt = None;
+            return Ok(true);
+        }
+        self.last_click_opt = Some((Instant::now(), selection_pos));
+        if selection_pos != self.selection_pos {
+            self.selection_pos = selection_pos;
+            self.switch_session(false)?;
+        }
+        Ok(false)
+    }
+
+    /// Moves the cursor to the clicked grapheme of the pattern.
+    pub fn click_pattern(&mut self, width: usize) {
+        self.pattern.set_cursor_width(width);
+    }
+
     pub fn is_selected(&self, i: usize) -> bool {
-        i == self.selection_pos - self.scroll_pos
+        self.selection_pos.checked_sub(self.scroll_pos) == Some(i)
     }
 
-    pub fn confirm(&self) -> anyhow::Result<bool> {
-        self.switch_session(true)?;
-        Ok(!self.matches.is_empty())
+    pub fn selected_session(&self) -> Option<&Session> {
+        match self.get_selected_item() {
+            Ok(Some(Item::Session(session))) => Some(session),
+            _ => None,
+        }
+    }
+
+    /// Returns the contents of the selected session's active pane, which are
+    /// only captured again once another session is selected or the refresh
+    /// interval of the preview has elapsed.
+    pub fn preview(&mut self) -> anyhow::Result<Option<&str>> {
+        let Some(session) = self.selected_session() else {
+            return Ok(None);
+        };
+        let refresh_interval =
+            Duration::from_millis(self.config.session_selector.preview.refresh_interval.get());
+        let is_stale = self
+            .preview_opt
+            .as_ref()
+            .is_none_or(|(session_id, captured_at, _)| {
+                session_id != session.id() || captured_at.elapsed() >= refresh_interval
+            });
+        if is_stale {
+            let contents = session.capture_active_pane()?;
+            self.preview_opt = Some((session.id().to_string(), Instant::now(), contents));
+        }
+        Ok(self
+            .preview_opt
+            .as_ref()
+            .map(|(_, _, contents)| contents.as_str()))
+    }
+
+    pub fn confirm(&mut self) -> anyhow::Result<bool> {
+        match self.get_selected_item()? {
+            Some(Item::Project(project)) => {
+                let project = project.clone();
+                self.create_project(project)
+            }
+            Some(Item::NewSession(path)) => match path.try_exists() {
+                Ok(true) => match command::prepare_path(path, false) {
+                    Ok(path) => self.create_project(path),
+                    Err(err) => Ok(self.report_error(&err)),
+                },
+                Ok(false) => {
+                    self.pending_action_opt = Some(PendingAction::CreateDirs);
+                    Ok(false)
+                }
+                Err(err) => Ok(self.report_error(&err.into())),
+            },
+            Some(Item::Directory(path)) => {
+                let path = match path.canonicalize() {
+                    Ok(path) => path,
+                    Err(err) => return Ok(self.report_error(&err.into())),
+                };
+                if let Some(session) = self.sessions.iter().find(|session| session.path() == &path)
+                {
+                    self.switch_to_session(session.clone(), true)?;
+                    return Ok(true);
+                }
+                self.create_project(path)
+            }
+            _ => {
+                self.switch_session(true)?;
+                Ok(!self.matches.is_empty())
+            }
+        }
     }
 
     pub fn abort(&self) -> anyhow::Result<()> {
+        // Without live switching the initial session has never been left.
+        if self.live_switch() == LiveSwitch::Never {
+            return Ok(());
+        }
         if let Some(initial_session) = &self.initial_session_opt {
             return initial_session.switch_to(self.args.target_client.as_ref());
         }
         Ok(())
     }
 
+    /// Returns whether the results are the subdirectories of the pattern as a
+    /// path instead of the sessions and projects, which is the case in the
+    /// browse mode or once a path is typed with a trailing slash.
+    pub fn is_browsing(&self) -> bool {
+        self.is_browse_mode || (self.is_path_typed() && self.pattern.text().ends_with('/'))
+    }
+
+    /// Switches between matching the sessions and projects and browsing the
+    /// subdirectories of the pattern as a path.
+    pub fn toggle_browse(&mut self) -> anyhow::Result<()> {
+        self.is_browse_mode = !self.is_browse_mode;
+        self.match_sessions()
+    }
+
+    /// Completes the last component of the path, which has been typed as
+    /// pattern, to the longest common prefix of the subdirectories starting
+    /// with it or otherwise, while browsing, to the selected subdirectory.
+    pub fn complete_path(&mut self) -> anyhow::Result<()> {
+        if !self.is_browsing() && !self.is_path_typed() {
+            return Ok(());
+        }
+        let pattern = self.pattern_string();
+        if matches!(pattern.as_str(), "~" | "." | "..") {
+            self.pattern.set_text(&format!("{pattern}/"));
+            return self.match_sessions();
+        }
+        let selected_name_opt = if self.is_browsing() {
+            match self.get_selected_item()? {
+                Some(Item::Directory(path)) => path
+                    .file_name()
+                    .map(|name| format!("{}/", name.to_string_lossy())),
+                _ => None,
+            }
+        } else {
+            // The subdirectories are only read while browsing.
+            self.match_directories()?;
+            None
+        };
+        let (parent, component) = split_path(&pattern);
+        let names = self
+            .directory_names
+            .iter()
+            .map(|name| name.trim_end_matches('/'))
+            .filter(|name| name.starts_with(component))
+            .collect::<Vec<_>>();
+        let completion = match names.as_slice() {
+            [] => match selected_name_opt {
+                Some(selected_name) => selected_name,
+                None => return self.match_sessions(),
+            },
+            [name] => format!("{name}/"),
+            [name, names @ ..] => names.iter().fold((*name).to_string(), |prefix, name| {
+                prefix
+                    .chars()
+                    .zip(name.chars())
+                    .take_while(|(c_a, c_b)| c_a == c_b)
+                    .map(|(c, _)| c)
+                    .collect()
+            }),
+        };
+        self.pattern.set_text(&format!("{parent}{completion}"));
+        self.match_sessions()
+    }
+
+    /// Toggles the mark of the selected session, so batch actions are
+    /// performed on it, and selects the next or previous result.
+    pub fn toggle_mark(&mut self, is_next: bool) -> anyhow::Result<()> {
+        let Some(session_id) = self
+            .selected_session()
+            .map(|session| session.id().to_string())
+        else {
+            return Ok(());
+        };
+        if !self.marked_session_ids.remove(&session_id) {
+            self.marked_session_ids.insert(session_id);
+        }
+        if is_next {
+            self.selection_next()
+        } else {
+            self.selection_prev()
+        }
+    }
+
+    pub fn marks_len(&self) -> usize {
+        self.marked_session_ids.len()
+    }
+
+    pub fn is_marked_by_index(&self, i: usize) -> bool {
+        self.sessions
+            .get(i)
+            .is_some_and(|session| self.marked_session_ids.contains(session.id()))
+    }
+
+    /// Requests the given action on the marked sessions or, if there are none,
+    /// the selected session, which is only performed after it has been
+    /// confirmed.
+    pub fn request_action(&mut self, action: PendingAction) {
+        if !self.action_targets().is_empty() {
+            self.pending_action_opt = Some(action);
+            self.pending_input.clear();
+        }
+    }
+
+    pub fn is_action_pending(&self) -> bool {
+        self.pending_action_opt.is_some()
+    }
+
+    /// Returns whether the pending action requires text input instead of a
+    /// yes or no answer.
+    pub fn is_input_pending(&self) -> bool {
+        matches!(self.pending_action_opt, Some(PendingAction::SendKeys))
+    }
+
+    pub fn pending_input_add(&mut self, char: char) {
+        self.pending_input.push(char);
+    }
+
+    pub fn pending_input_add_str(&mut self, text: &str) {
+        self.pending_input.push_str(text);
+    }
+
+    pub fn pending_input_delete(&mut self) {
+        self.pending_input.pop();
+    }
+
+    pub fn error(&self) -> Option<&str> {
+        self.error_opt.as_deref()
+    }
+
+    /// Dismisses the displayed error and returns whether there has been one.
+    pub fn dismiss_error(&mut self) -> bool {
+        self.error_opt.take().is_some()
+    }
+
+    /// Displays the error in the prompt and returns that the session selector
+    /// should stay open.
+    fn report_error(&mut self, err: &anyhow::Error) -> bool {
+        self.error_opt = Some(format!("Error: {err}"));
+        false
+    }
+
+    /// Returns the confirmation question for the pending action.
+    pub fn pending_action_question(&self) -> Option<String> {
+        let action = self.pending_action_opt?;
+        let targets_display_fn = || match self.action_targets().as_slice() {
+            [session] => format!("session '{}'", session.name()),
+            sessions => format!("{} marked sessions", sessions.len()),
+        };
+        let question = match action {
+            PendingAction::Kill => format!("Kill {}? [y/N]", targets_display_fn()),
+            PendingAction::DetachOtherClients => {
+                format!("Detach other clients from {}? [y/N]", targets_display_fn())
+            }
+            PendingAction::SendKeys => {
+                format!(
+                    "Send keys to {}: {}",
+                    targets_display_fn(),
+                    self.pending_input
+                )
+            }
+            PendingAction::CreateDirs => format!(
+                "Create directory '{}'? [y/N]",
+                self.new_session_path_opt.as_ref()?.to_string_lossy()
+            ),
+        };
+        Some(question)
+    }
+
+    /// Performs the pending action, if it has been confirmed, and returns
+    /// whether the session selector should be closed.
+    pub fn answer_pending_action(&mut self, is_confirmed: bool) -> anyhow::Result<bool> {
+        let Some(action) = self.pending_action_opt.take() else {
+            return Ok(false);
+        };
+        if !is_confirmed {
+            return Ok(false);
+        }
+        let targets = self.action_targets();
+        match action {
+            PendingAction::CreateDirs => {
+                let Some(new_session_path) = &self.new_session_path_opt else {
+                    return Ok(false);
+                };
+                return match command::prepare_path(new_session_path, true) {
+                    Ok(path) => self.create_project(path),
+                    Err(err) => Ok(self.report_error(&err)),
+                };
+            }
+            PendingAction::Kill => self.kill_sessions(&targets)?,
+            PendingAction::DetachOtherClients => {
+                for session in &targets {
+                    session.detach_other_clients(self.args.target_client.as_ref())?;
+                }
+            }
+            PendingAction::SendKeys => {
+                for session in &targets {
+                    session.send_command(&self.pending_input)?;
+                }
+            }
+        }
+        Ok(false)
+    }
+
+    /// Marks the project's session to be created after the session selector
+    /// has been closed.
+    fn create_project(&mut self, project: PathBuf) -> anyhow::Result<bool> {
+        // Switch back to the initial session, so it is the current one when
+        // the project's session is created.
+        self.abort()?;
+        self.record_query()?;
+        self.project_to_create_opt = Some(project);
+        Ok(true)
+    }
+
+    /// Returns the sessions, which actions are performed on.
+    fn action_targets(&self) -> Vec<Session> {
+        if self.marked_session_ids.is_empty() {
+            return self.selected_session().cloned().into_iter().collect();
+        }
+        self.sessions
+            .iter()
+            .filter(|session| self.marked_session_ids.contains(session.id()))
+            .cloned()
+            .collect()
+    }
+
+    fn kill_sessions(&mut self, sessions_to_kill: &[Session]) -> anyhow::Result<()> {
+        // The session selector's client is switched away from the sessions
+        // before killing them, preferably back to the initial session.
+        let session_switched_to_opt = command::kill_sessions(
+            self.args,
+            &self.sessions,
+            sessions_to_kill,
+            self.initial_session_opt.as_ref(),
+        )?;
+        if let Some(initial_session) = &self.initial_session_opt
+            && sessions_to_kill.contains(initial_session)
+        {
+            self.initial_session_opt = session_switched_to_opt;
+        }
+        // Sessions are located at the beginning of the items, so their indices
+        // are the same in all item vectors.
+        for i in (0..self.sessions.len()).rev() {
+            if sessions_to_kill.contains(&self.sessions[i]) {
+                self.marked_session_ids.remove(self.sessions[i].id());
+                self.sessions.remove(i);
+                self.item_paths.remove(i);
+                self.item_frecency_bonuses.remove(i);
+                if i < self.item_git_branches.len() {
+                    self.item_git_branches.remove(i);
+                }
+            }
+        }
+        self.match_sessions()
+    }
+
+    pub fn sort_mode(&self) -> SortMode {
+        self.sort_mode
+    }
+
+    pub fn cycle_sort_mode(&mut self) -> anyhow::Result<()> {
+        self.sort_mode = self.sort_mode.next();
+        self.match_sessions()
+    }
+
+    pub fn matching_mode(&self) -> MatchingMode {
+        self.matching_mode
+    }
+
+    pub fn cycle_matching_mode(&mut self) -> anyhow::Result<()> {
+        self.matching_mode = self.matching_mode.next();
+        self.match_sessions()
+    }
+
+    /// Returns all items sorted by the sort mode, which is used while the
+    /// pattern is empty.
+    fn sorted_items(&self) -> Vec<(usize, Vec<usize>)> {
+        let mut indices = (0..self.items_len()).collect::<Vec<_>>();
+        match self.sort_mode {
+            SortMode::Frecency => indices.sort_by(|i_a, i_b| {
+                self.item_frecency_bonuses[*i_b].total_cmp(&self.item_frecency_bonuses[*i_a])
+            }),
+            SortMode::Path => indices.sort_by_key(|i| {
+                self.sessions
+                    .get(*i)
+                    .map_or_else(|| &self.projects[*i - self.sessions.len()], Session::path)
+            }),
+            SortMode::LastAttached | SortMode::Activity | SortMode::Created => {
+                // Projects have no session times, so they are ordered after
+                // the sessions.
+                indices.sort_by_key(|i| {
+                    Reverse(self.sessions.get(*i).map(|session| match self.sort_mode {
+                        SortMode::LastAttached => session.last_attached(),
+                        SortMode::Activity => session.activity(),
+                        _ => session.created(),
+                    }))
+                });
+            }
+        }
+        indices.into_iter().map(|i| (i, Vec::new())).collect()
+    }
+
+    /// Returns whether the pattern is an absolute path or a path relative to
+    /// the home directory or explicitly to the current directory.
+    pub fn is_path_typed(&self) -> bool {
+        let pattern = self.pattern.text();
+        pattern.starts_with(['/', '~'])
+            || pattern == "."
+            || pattern == ".."
+            || pattern.starts_with("./")
+            || pattern.starts_with("../")
+    }
+
+    /// Resolves the path, which has been typed as pattern, relative to the
+    /// path of the initial session.
+    fn resolve_path(&self, path: &str) -> anyhow::Result<PathBuf> {
+        let base_path = match &self.initial_session_opt {
+            Some(initial_session) => initial_session.path().clone(),
+            None => std::env::current_dir()?,
+        };
+        let path = base_path.join(path::expand_home_dir(path));
+        Ok(path
+            .canonicalize()
+            .unwrap_or_else(|_| normalize_path(&path)))
+    }
+
+    /// Matches the subdirectories of the path, which has been typed as
+    /// pattern, against its last component.
+    fn match_directories(&mut self) -> anyhow::Result<()> {
+        self.directories.clear();
+        self.directory_names.clear();
+        self.matches.clear();
+        let pattern = self.pattern_string();
+        let (parent, component) = split_path(&pattern);
+        let Ok(entries) = self.resolve_path(parent)?.read_dir() else {
+            return Ok(());
+        };
+        let browser = &self.config.session_selector.browser;
+        let mut directories = entries
+            .filter_map(|entry| {
+                let path = entry.ok()?.path();
+                let name = path.file_name()?.to_string_lossy().to_string();
+                // Hidden directories are still listed, if the component
+                // explicitly starts with a dot.
+                if name.starts_with('.') && !browser.hidden && !component.starts_with('.') {
+                    return None;
+                }
+                if browser.ignore.contains(&name) || !path.is_dir() {
+                    return None;
+                }
+                Some((name, path))
+            })
+            .collect::<Vec<_>>();
+        directories.sort();
+        (self.directory_names, self.directories) = directories
+            .into_iter()
+            .map(|(name, path)| (format!("{name}/"), path))
+            .unzip();
+
+        let offset = self.items_len() + 1;
+        if component.is_empty() {
+            self.matches = (0..self.directories.len())
+                .map(|i| (offset + i, Vec::new()))
+                .collect();
+        } else {
+            self.matches = matching::matches(
+                component,
+                &self.directory_names,
+                &self.config.session_selector.matching,
+                self.matching_mode,
+            )
+            .into_iter()
+            .map(|m| (offset + m.index_in_haystack, m.indices.unwrap_or_default()))
+            .collect();
+        }
+        Ok(())
+    }
+
+    /// Offers the session for the path, which has been typed as pattern, as the
+    /// last result or, if no session exists for it, a new session.
+    fn match_new_session(&mut self) -> anyhow::Result<()> {
+        self.new_session_path_opt = None;
+        let path = self.resolve_path(&self.pattern_string())?;
+        if let Some(i) = self
+            .sessions
+            .iter()
+            .position(|session| session.path() == &path)
+        {
+            // The session may already be matched by its path.
+            if self.matches.iter().all(|(j, _)| *j != i) {
+                self.matches.push((i, Vec::new()));
+            }
+            return Ok(());
+        }
+        self.new_session_label = format!("create session at {}", path.to_string_lossy());
+        self.new_session_path_opt = Some(path);
+        self.matches.push((self.items_len(), Vec::new()));
+        Ok(())
+    }
+
     fn match_sessions(&mut self) -> anyhow::Result<()> {
         self.scroll_pos = 0;
         self.selection_pos = 0;
+        self.query_pos_opt = None;
 
-        self.matches = frizbee::match_list(
-            self.pattern_string(),
-            &self.session_paths,
-            #[allow(clippy::cast_possible_truncation)]
-            frizbee::Options {
-                min_score: self.pattern.len() as u16 * 6,
-                max_typos: Some(self.pattern.len() as u16 / 4),
-                matched_indices: true,
-                ..Default::default()
-            },
-        )
-        .iter()
-        .map(|m| (m.index_in_haystack, m.indices.clone().unwrap_or_default()))
-        .collect();
+        self.new_session_path_opt = None;
+        if self.is_browsing() {
+            self.match_directories()?;
+            self.match_new_session()?;
+            return self.switch_session(false);
+        }
+        if self.pattern.is_empty() {
+            self.matches = self.sorted_items();
+            return self.switch_session(false);
+        }
+
+        let mut matches = matching::matches(
+            &self.pattern_string(),
+            &self.item_paths,
+            &self.config.session_selector.matching,
+            self.matching_mode,
+        );
+        // Blend the frecency of the items into the fuzzy matching scores.
+        let score_fn = |m: &frizbee::Match| {
+            f64::from(m.score) + self.item_frecency_bonuses[m.index_in_haystack]
+        };
+        matches.sort_by(|m_a, m_b| score_fn(m_b).total_cmp(&score_fn(m_a)));
+        self.matches = matches
+            .iter()
+            .map(|m| (m.index_in_haystack, m.indices.clone().unwrap_or_default()))
+            .collect();
+        if self.is_path_typed() {
+            self.match_new_session()?;
+        }
         self.switch_session(false)
     }
 
-    fn switch_session(&self, save_initial_as_last: bool) -> anyhow::Result<()> {
-        let Some(selected_session) = self.get_selected_session()? else {
+    /// Returns whether switching to the selected session has been deferred
+    /// until no more input is received.
+    pub fn is_switch_pending(&self) -> bool {
+        self.is_switch_pending
+    }
+
+    /// Performs the deferred switch to the selected session.
+    pub fn switch_pending_session(&mut self) -> anyhow::Result<()> {
+        self.is_switch_pending = false;
+        let Some(Item::Session(selected_session)) = self.get_selected_item()? else {
             return Ok(());
         };
-        if save_initial_as_last
-            && let Some(initial_session) = &self.initial_session_opt
-            && initial_session != selected_session
-        {
-            initial_session.save_as_last()?;
+        self.switch_to_session(selected_session.clone(), false)
+    }
+
+    fn switch_session(&mut self, is_confirmed: bool) -> anyhow::Result<()> {
+        self.is_switch_pending = false;
+        if !is_confirmed {
+            match self.live_switch() {
+                LiveSwitch::Always => (),
+                LiveSwitch::Debounced => {
+                    self.is_switch_pending = true;
+                    return Ok(());
+                }
+                LiveSwitch::Never => return Ok(()),
+            }
+        }
+        let Some(Item::Session(selected_session)) = self.get_selected_item()? else {
+            return Ok(());
+        };
+        self.switch_to_session(selected_session.clone(), is_confirmed)
+    }
+
+    /// Returns the live switching mode, which is always never if switching is
+    /// deferred.
+    fn live_switch(&self) -> LiveSwitch {
+        if self.is_switch_deferred {
+            LiveSwitch::Never
+        } else {
+            self.config.session_selector.live_switch
+        }
+    }
+
+    fn switch_to_session(&mut self, session: Session, is_confirmed: bool) -> anyhow::Result<()> {
+        if is_confirmed {
+            History::record_switch(self.initial_session_opt.as_ref(), &session)?;
+            frecency::record(session.path())?;
+            self.record_query()?;
+        }
+        if self.is_switch_deferred {
+            self.session_to_switch_to_opt = Some(session);
+        } else {
+            session.switch_to(self.args.target_client.as_ref())?;
         }
-        selected_session.switch_to(self.args.target_client.as_ref())?;
         Ok(())
     }
 
-    fn get_selected_session(&self) -> anyhow::Result<Option<&Session>> {
+    /// Records the pattern in the query history, as it led to a confirmed
+    /// switch.
+    fn record_query(&self) -> anyhow::Result<()> {
+        if self.pattern.is_empty() {
+            return Ok(());
+        }
+        query_history::record(
+            &self.pattern_string(),
+            &self.config.session_selector.query_history,
+        )
+    }
+
+    /// Returns whether the pattern is empty or has been recalled from the
+    /// query history and not been edited since, so the previous query can be
+    /// recalled instead of selecting a result.
+    pub fn is_query_recallable(&self) -> bool {
+        !self.queries.is_empty() && (self.pattern.is_empty() || self.query_pos_opt.is_some())
+    }
+
+    /// Replaces the pattern with the query before the currently recalled one
+    /// in the query history, cycling back to the most recent one.
+    pub fn recall_query(&mut self) -> anyhow::Result<()> {
+        if self.queries.is_empty() {
+            return Ok(());
+        }
+        let query_pos = match self.query_pos_opt {
+            Some(0) | None => self.queries.len() - 1,
+            Some(query_pos) => query_pos - 1,
+        };
+        self.pattern.set_text(&self.queries[query_pos]);
+        self.match_sessions()?;
+        self.query_pos_opt = Some(query_pos);
+        Ok(())
+    }
+
+    fn get_selected_item(&self) -> anyhow::Result<Option<Item<'_>>> {
         if self.matches.is_empty() {
             return Ok(None);
         }
@@ -205,10 +1178,125 @@ impl<'a> State<'a> {
             .matches
             .get(self.selection_pos)
             .ok_or(anyhow::format_err!("selected match result does not exist"))?;
-        let session = self
-            .sessions
-            .get(*i)
-            .ok_or(anyhow::format_err!("selected session does not exist"))?;
-        Ok(Some(session))
+        if let Some(session) = self.sessions.get(*i) {
+            return Ok(Some(Item::Session(session)));
+        }
+        if *i == self.items_len()
+            && let Some(new_session_path) = &self.new_session_path_opt
+        {
+            return Ok(Some(Item::NewSession(new_session_path)));
+        }
+        if *i > self.items_len() {
+            let directory = self
+                .directories
+                .get(*i - self.items_len() - 1)
+                .ok_or(anyhow::format_err!("selected directory does not exist"))?;
+            return Ok(Some(Item::Directory(directory)));
+        }
+        let project = self
+            .projects
+            .get(*i - self.sessions.len())
+            .ok_or(anyhow::format_err!("selected item does not exist"))?;
+        Ok(Some(Item::Project(project)))
+    }
+}
+
+/// Resolves the `.` and `..` components of the path lexically, as it may not
+/// exist yet.
+fn normalize_path(path: &Path) -> PathBuf {
+    let mut path_normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => (),
+            Component::ParentDir => {
+                path_normalized.pop();
+            }
+            component => path_normalized.push(component),
+        }
+    }
+    path_normalized
+}
+
+/// Splits the path into its parent including the trailing slash and its last
+/// component.
+fn split_path(path: &str) -> (&str, &str) {
+    path.rfind('/')
+        .map_or(("", path), |pos| path.split_at(pos + 1))
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::Parser as _;
+
+    use super::*;
+
+    fn state_with_projects<'a>(args: &'a Args, config: &'a Config, count: usize) -> State<'a> {
+        let projects = (0..count)
+            .map(|i| PathBuf::from(format!("/projects/{i:03}")))
+            .collect();
+        let mut state = State::with_items(
+            args,
+            config,
+            true,
+            None,
+            Vec::new(),
+            projects,
+            &Frecency::default(),
+        );
+        state.sort_mode = SortMode::Path;
+        state.matches = state.sorted_items();
+        state
+    }
+
+    fn assert_selection_visible(state: &State, item_count: usize, scrolloff: usize) {
+        assert!(state.scroll_pos + scrolloff <= state.selection_pos);
+        assert!(state.selection_pos < state.scroll_pos + item_count - scrolloff);
+        assert!(state.is_selected(state.selection_pos - state.scroll_pos));
+    }
+
+    #[test]
+    fn selection_move_keeps_selection_visible() -> anyhow::Result<()> {
+        let args = Args::parse_from(["tmuxion", "select"]);
+        let config = Config::default();
+        let mut state = state_with_projects(&args, &config, 100);
+
+        state.selection_edge(false)?;
+        state.adjust_scroll_pos(20, 4);
+        for _ in 0..2 {
+            state.selection_move(42, true)?;
+            state.adjust_scroll_pos(20, 4);
+            assert_selection_visible(&state, 20, 4);
+        }
+        assert_eq!(state.selection_pos, 84);
+        assert_eq!(state.scroll_pos, 69);
+
+        state.selection_move(42, false)?;
+        state.adjust_scroll_pos(20, 4);
+        assert_selection_visible(&state, 20, 4);
+        assert_eq!(state.scroll_pos, 38);
+        Ok(())
+    }
+
+    #[test]
+    fn new_session_and_directories_are_not_projects() {
+        let args = Args::parse_from(["tmuxion", "select"]);
+        let config = Config::default();
+        let state = state_with_projects(&args, &config, 2);
+
+        assert!(state.is_project_by_index(0));
+        assert!(state.is_project_by_index(1));
+        assert!(!state.is_project_by_index(2));
+        assert!(!state.is_project_by_index(3));
+    }
+
+    #[test]
+    fn is_selected_is_false_above_the_scroll_pos() {
+        let args = Args::parse_from(["tmuxion", "select"]);
+        let config = Config::default();
+        let mut state = state_with_projects(&args, &config, 100);
+
+        state.selection_pos = 3;
+        state.scroll_pos = 8;
+        assert!(!state.is_selected(0));
     }
 }
@@ -1,12 +1,25 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use mlua::{FromLua, IntoLua, Lua, LuaSerdeExt as _};
 use ratatui::{
     prelude::*,
-    widgets::{Block, Borders, List, ListDirection, ListItem},
+    widgets::{Block, Borders, List, ListDirection, ListItem, Paragraph},
 };
+use serde::Deserialize;
+use tmux_interface::Size;
 use unicode_segmentation::UnicodeSegmentation as _;
+use unicode_width::UnicodeWidthStr as _;
 
-use crate::config::Config;
+use crate::{
+    config::{Config, PreviewPosition, ResultsColumn},
+    deserializers,
+    tmux::Session,
+};
 
-use super::state::State;
+use super::{
+    ansi,
+    state::{Mode, State},
+};
 
 pub fn draw(config: &Config, state: &mut State, frame: &mut Frame) -> std::io::Result<()> {
     let mut constraints = [Constraint::Percentage(100), Constraint::Min(3)];
@@ -20,27 +33,73 @@ pub fn draw(config: &Config, state: &mut State, frame: &mut Frame) -> std::io::R
         (layout[0], layout[1])
     };
 
-    draw_results(config, state, frame, area_results);
+    if config.session_selector.preview.enabled {
+        #[allow(clippy::cast_possible_truncation)]
+        let constraint_preview = match config.session_selector.preview.size {
+            Size::Size(size) => Constraint::Length(size as u16),
+            Size::Percentage(percentage) => Constraint::Percentage(percentage as u16),
+        };
+        // The results keep at least a single row besides their borders.
+        let constraints = [Constraint::Min(3), constraint_preview];
+        let layout = match config.session_selector.preview.position {
+            PreviewPosition::Right => Layout::horizontal(constraints),
+            PreviewPosition::Bottom => Layout::vertical(constraints),
+        }
+        .split(area_results);
+        draw_results(config, state, frame, layout[0])?;
+        draw_preview(config, state, frame, layout[1])?;
+    } else {
+        draw_results(config, state, frame, area_results)?;
+    }
     draw_prompt(config, state, frame, area_prompt)?;
 
     Ok(())
 }
 
-fn draw_results(config: &Config, state: &mut State, frame: &mut Frame, area: Rect) {
-    state.adjust_scroll_pos(area.height as usize - 2, config.session_selector.scrolloff);
+fn draw_results(
+    config: &Config,
+    state: &mut State,
+    frame: &mut Frame,
+    area: Rect,
+) -> std::io::Result<()> {
+    // The borders take up two rows, which may not even fit into the area.
+    let item_count = usize::from(area.height.saturating_sub(2));
+    state.adjust_scroll_pos(item_count, config.session_selector.scrolloff);
+
+    let visible_matches = state.visible_matches(item_count);
+    let rows_segments = if let Some(item_format) = &config.session_selector.results.item_format {
+        visible_matches
+            .iter()
+            .map(|(i, _)| item_format.call::<Vec<Segment>>(ItemInfo::new(state, *i)))
+            .collect::<mlua::Result<Vec<_>>>()
+            .map_err(std::io::Error::other)?
+    } else {
+        get_columns_segments(config, state, visible_matches)
+    };
+    // The paths are padded over all matches like the columns, so the segments
+    // following them are aligned.
+    let path_width = state
+        .matches()
+        .iter()
+        .filter_map(|(i, _)| state.get_item_path_by_index(*i))
+        .map(|item_path| item_path.width())
+        .max()
+        .unwrap_or_default();
 
-    let items = state
-        .visible_matches(area.height as usize - 2)
+    let items = visible_matches
         .iter()
-        .map(|(i, matched_indices)| {
-            let session_path = state
-                .get_session_path_by_index(*i)
-                .expect("session at index should always exist");
-            (session_path, matched_indices)
-        })
+        .zip(rows_segments)
         .enumerate()
-        .map(|(i, (session_path, matched_indices))| {
-            get_results_item(config, session_path, matched_indices, state.is_selected(i))
+        .map(|(pos, ((i, matched_indices), segments))| {
+            get_results_item(
+                config,
+                state,
+                *i,
+                matched_indices,
+                &segments,
+                path_width,
+                state.is_selected(pos),
+            )
         })
         .collect::<Vec<_>>();
     let block = Block::new()
@@ -51,6 +110,7 @@ fn draw_results(config: &Config, state: &mut State, frame: &mut Frame, area: Rec
         .title(config.session_selector.results.title.clone())
         .title_alignment(config.session_selector.results.title_alignment)
         .title_style(config.session_selector.results.title_style);
+    state.set_results_area(block.inner(area));
     let list = List::new(items)
         .block(block)
         .direction(if config.session_selector.inverted {
@@ -59,15 +119,101 @@ fn draw_results(config: &Config, state: &mut State, frame: &mut Frame, area: Rec
             ListDirection::BottomToTop
         });
     frame.render_widget(list, area);
+
+    Ok(())
+}
+
+/// Returns the segments of the rows consisting of the path followed by the
+/// configured columns, which are padded to the same width.
+fn get_columns_segments(
+    config: &Config,
+    state: &State,
+    visible_matches: &[(usize, Vec<usize>)],
+) -> Vec<Vec<Segment>> {
+    // The widths are the same for all matches, so they do not change while
+    // scrolling.
+    let mut column_widths = vec![0; config.session_selector.results.columns.len()];
+    for (i, _) in state.matches() {
+        for (column_width, text) in column_widths
+            .iter_mut()
+            .zip(get_column_texts(config, state, *i))
+        {
+            *column_width = text.width().max(*column_width);
+        }
+    }
+
+    visible_matches
+        .iter()
+        .map(|(i, _)| {
+            let texts = get_column_texts(config, state, *i);
+            let columns_segments = texts.into_iter().zip(&column_widths).map(|(text, width)| {
+                let padding = width - text.width();
+                Segment::Text(
+                    format!(" {text}{}", " ".repeat(padding)),
+                    config.session_selector.results.column_style,
+                )
+            });
+            std::iter::once(Segment::Path)
+                .chain(columns_segments)
+                .collect()
+        })
+        .collect()
+}
+
+/// Returns the texts of the configured columns of the item.
+fn get_column_texts(config: &Config, state: &State, i: usize) -> Vec<String> {
+    let session_opt = state.get_session_by_index(i);
+    config
+        .session_selector
+        .results
+        .columns
+        .iter()
+        .map(|column| match column {
+            ResultsColumn::Windows => session_opt
+                .map(|session| format!("{}w", session.windows()))
+                .unwrap_or_default(),
+            ResultsColumn::Attached => session_opt
+                .map(|session| format!("{}a", session.attached()))
+                .unwrap_or_default(),
+            ResultsColumn::GitBranch => state
+                .get_git_branch_by_index(i)
+                .cloned()
+                .unwrap_or_default(),
+            ResultsColumn::Idle => session_opt
+                .map(|session| format_idle(session.activity()))
+                .unwrap_or_default(),
+        })
+        .collect()
+}
+
+/// Formats the time since the last activity with the largest fitting unit.
+fn format_idle(activity: u64) -> String {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    let idle = now.saturating_sub(activity);
+    match idle {
+        0..60 => format!("{idle}s"),
+        60..3_600 => format!("{}m", idle / 60),
+        3_600..86_400 => format!("{}h", idle / 3_600),
+        _ => format!("{}d", idle / 86_400),
+    }
 }
 
 fn get_results_item<'a>(
     config: &'a Config,
-    session_path: &'a str,
+    state: &State,
+    i: usize,
     matched_indices: &[usize],
+    segments: &[Segment],
+    path_width: usize,
     is_selected: bool,
 ) -> ListItem<'a> {
-    let mut spans = Vec::with_capacity(session_path.len() + 1);
+    let item_path = state
+        .get_item_path_by_index(i)
+        .expect("item at index should always exist");
+    let mut spans = Vec::with_capacity(item_path.len() + segments.len() + 2);
 
     spans.push(if is_selected {
         Span::styled(
@@ -84,15 +230,56 @@ fn get_results_item<'a>(
         Span::raw(String::from(' ').repeat(selection_prefix_len))
     });
 
-    for (i, c) in session_path.chars().enumerate() {
-        let mut style = config.session_selector.results.item_style;
-        if is_selected {
-            style = style.patch(config.session_selector.results.selection_style);
+    // The column of the markers is only displayed, if there are marked items.
+    if state.marks_len() > 0 {
+        if state.is_marked_by_index(i) {
+            spans.push(Span::styled(
+                config.session_selector.results.marker.clone(),
+                config.session_selector.results.marker_style,
+            ));
+        } else {
+            let marker_len = config
+                .session_selector
+                .results
+                .marker
+                .graphemes(true)
+                .count();
+            spans.push(Span::raw(String::from(' ').repeat(marker_len)));
         }
-        if matched_indices.binary_search(&i).is_ok() {
-            style = style.patch(config.session_selector.results.item_match_style);
+    }
+
+    let mut item_style = config.session_selector.results.item_style;
+    if state.is_project_by_index(i) {
+        item_style = item_style.patch(config.session_selector.results.item_project_style);
+    }
+    if is_selected {
+        item_style = item_style.patch(config.session_selector.results.selection_style);
+    }
+    for (segment_pos, segment) in segments.iter().enumerate() {
+        match segment {
+            Segment::Path => {
+                // The matched indices refer to the characters of the path, so
+                // they are highlighted regardless of the preceding segments.
+                for (char_pos, c) in item_path.chars().enumerate() {
+                    let mut style = item_style;
+                    if matched_indices.binary_search(&char_pos).is_ok() {
+                        style = style.patch(config.session_selector.results.item_match_style);
+                    }
+                    spans.push(Span::styled(String::from(c), style));
+                }
+                if segment_pos + 1 < segments.len() {
+                    let padding = path_width.saturating_sub(item_path.width());
+                    spans.push(Span::styled(" ".repeat(padding), item_style));
+                }
+            }
+            Segment::Text(text, style) => {
+                let mut style = item_style.patch(*style);
+                if is_selected {
+                    style = style.patch(config.session_selector.results.selection_style);
+                }
+                spans.push(Span::styled(text.clone(), style));
+            }
         }
-        spans.push(Span::styled(String::from(c), style));
     }
 
     let mut list_item = ListItem::new(Line::from(spans));
@@ -102,9 +289,145 @@ fn get_results_item<'a>(
     list_item
 }
 
+/// A segment of a result row.
+enum Segment {
+    /// The path of the item, which contains the highlighted matches.
+    Path,
+    Text(String, Style),
+}
+
+impl FromLua for Segment {
+    fn from_lua(value: mlua::Value, lua: &Lua) -> mlua::Result<Self> {
+        #[derive(Deserialize)]
+        struct SegmentIntermediate {
+            #[serde(default)]
+            path: bool,
+            #[serde(default)]
+            text: String,
+            #[serde(default, deserialize_with = "deserializers::style")]
+            style: Style,
+        }
+
+        if let mlua::Value::String(text) = &value {
+            return Ok(Self::Text(text.to_str()?.to_string(), Style::new()));
+        }
+        let segment = lua.from_value::<SegmentIntermediate>(value)?;
+        if segment.path {
+            return Ok(Self::Path);
+        }
+        Ok(Self::Text(segment.text, segment.style))
+    }
+}
+
+/// The information about an item, which is passed to the Lua function
+/// formatting the result rows.
+struct ItemInfo<'a> {
+    path: &'a str,
+    is_project: bool,
+    session_opt: Option<&'a Session>,
+    git_branch_opt: Option<&'a String>,
+}
+
+impl<'a> ItemInfo<'a> {
+    fn new(state: &'a State, i: usize) -> Self {
+        Self {
+            path: state
+                .get_item_path_by_index(i)
+                .expect("item at index should always exist"),
+            is_project: state.is_project_by_index(i),
+            session_opt: state.get_session_by_index(i),
+            git_branch_opt: state.get_git_branch_by_index(i),
+        }
+    }
+}
+
+impl IntoLua for ItemInfo<'_> {
+    fn into_lua(self, lua: &Lua) -> mlua::Result<mlua::Value> {
+        let table = lua.create_table()?;
+        table.set("path", self.path)?;
+        table.set("is_project", self.is_project)?;
+        table.set("git_branch", self.git_branch_opt.map(String::as_str))?;
+        if let Some(session) = self.session_opt {
+            table.set("name", session.name())?;
+            table.set("windows", session.windows())?;
+            table.set("attached", session.attached())?;
+            table.set("activity", session.activity())?;
+            table.set("idle", format_idle(session.activity()))?;
+        }
+        Ok(mlua::Value::Table(table))
+    }
+}
+
+fn draw_preview(
+    config: &Config,
+    state: &mut State,
+    frame: &mut Frame,
+    area: Rect,
+) -> std::io::Result<()> {
+    let block = Block::new()
+        .style(config.session_selector.preview.style)
+        .borders(Borders::ALL)
+        .border_set(config.session_selector.preview.border)
+        .border_style(config.session_selector.preview.border_style)
+        .title(config.session_selector.preview.title.clone())
+        .title_alignment(config.session_selector.preview.title_alignment)
+        .title_style(config.session_selector.preview.title_style);
+    let area_inner = block.inner(area);
+    frame.render_widget(block, area);
+
+    let Some(output) = state.preview().map_err(std::io::Error::other)? else {
+        return Ok(());
+    };
+    // Show the end of the pane's contents, which is usually the most recent
+    // output, if it does not fit into the preview.
+    let mut text = ansi::to_text(output.trim_end());
+    let line_count = text.lines.len();
+    let visible_line_count = area_inner.height as usize;
+    if line_count > visible_line_count {
+        text.lines.drain(..line_count - visible_line_count);
+    }
+    frame.render_widget(Paragraph::new(text), area_inner);
+
+    Ok(())
+}
+
+/// Formats the stats displayed at the right of the prompt.
+fn format_stats(config: &Config, state: &State) -> std::io::Result<String> {
+    if let Some(stats_format) = &config.session_selector.prompt.stats_format {
+        return stats_format
+            .call((
+                state.matches_len(),
+                state.candidates_len(),
+                state.marks_len(),
+                state.sort_mode().as_str(),
+                state.matching_mode().as_str(),
+            ))
+            .map_err(std::io::Error::other);
+    }
+    let marks_display = if state.marks_len() > 0 {
+        format!("({}) ", state.marks_len())
+    } else {
+        String::new()
+    };
+    // The sort mode is only relevant while the pattern is empty and the
+    // matching mode otherwise.
+    let mode_display = if state.is_browsing() {
+        "browse"
+    } else if state.pattern_string().is_empty() {
+        state.sort_mode().as_str()
+    } else {
+        state.matching_mode().as_str()
+    };
+    Ok(format!(
+        " [{mode_display}] {}/{} {marks_display}",
+        state.matches_len(),
+        state.candidates_len()
+    ))
+}
+
 fn draw_prompt(
     config: &Config,
-    state: &State,
+    state: &mut State,
     frame: &mut Frame,
     area: Rect,
 ) -> std::io::Result<()> {
@@ -119,21 +442,17 @@ fn draw_prompt(
     let area_inner = block.inner(area);
     frame.render_widget(block, area);
 
-    let stats = if let Some(stats_format) = &config.session_selector.prompt.stats_format {
-        stats_format
-            .call((state.matches_len(), state.sessions_len()))
-            .map_err(std::io::Error::other)?
-    } else {
-        format!(" {}/{} ", state.matches_len(), state.sessions_len())
-    };
+    let stats = format_stats(config, state)?;
 
-    let pattern_prefix_len = config
-        .session_selector
-        .prompt
-        .pattern_prefix
-        .graphemes(true)
-        .count();
-    let stats_len = stats.graphemes(true).count();
+    let prompt = &config.session_selector.prompt;
+    // The editing mode is only displayed in the vi mode.
+    let mode_indicator = match state.mode() {
+        _ if !config.session_selector.vi_mode => "",
+        Mode::Insert => prompt.insert_mode_indicator.as_str(),
+        Mode::Normal => prompt.normal_mode_indicator.as_str(),
+    };
+    let pattern_prefix_len = mode_indicator.width() + prompt.pattern_prefix.width();
+    let stats_len = stats.width();
     let layout = Layout::horizontal([
         #[allow(clippy::cast_possible_truncation)]
         Constraint::Min(pattern_prefix_len as u16),
@@ -143,21 +462,41 @@ fn draw_prompt(
     ])
     .split(area_inner);
 
-    let span_pattern_prefix = Span::styled(
-        config.session_selector.prompt.pattern_prefix.clone(),
-        config
-            .session_selector
-            .prompt
-            .pattern_style
-            .patch(config.session_selector.prompt.pattern_prefix_style),
-    );
-    frame.render_widget(span_pattern_prefix, layout[0]);
+    let line_pattern_prefix = Line::from(vec![
+        Span::styled(
+            mode_indicator,
+            prompt.pattern_style.patch(prompt.mode_indicator_style),
+        ),
+        Span::styled(
+            prompt.pattern_prefix.as_str(),
+            prompt.pattern_style.patch(prompt.pattern_prefix_style),
+        ),
+    ]);
+    frame.render_widget(line_pattern_prefix, layout[0]);
 
-    let span_pattern = Span::styled(
-        state.pattern_string(),
-        config.session_selector.prompt.pattern_style,
-    );
+    // An error or the question of a pending action is displayed in place of
+    // the pattern.
+    let (text, style, cursor_width) = if let Some(error) = state.error() {
+        (
+            error.to_string(),
+            prompt.pattern_style.patch(prompt.error_style),
+            error.width(),
+        )
+    } else if let Some(question) = state.pending_action_question() {
+        let question_width = question.width();
+        (question, prompt.pattern_style, question_width)
+    } else {
+        state.adjust_pattern_scroll(layout[1].width.into());
+        let (visible_pattern, cursor_width) = state.visible_pattern();
+        (
+            visible_pattern.to_string(),
+            prompt.pattern_style,
+            cursor_width,
+        )
+    };
+    let span_pattern = Span::styled(text, style);
     frame.render_widget(span_pattern, layout[1]);
+    state.set_pattern_area(layout[1]);
 
     let span_stats = Span::styled(
         stats,
@@ -171,7 +510,7 @@ fn draw_prompt(
 
     #[allow(clippy::cast_possible_truncation)]
     frame.set_cursor_position((
-        layout[1].x + layout[1].width.min(state.cursor_pos() as u16),
+        layout[1].x + layout[1].width.min(cursor_width as u16),
         layout[1].y,
     ));
 
@@ -0,0 +1,22 @@
+use crate::{args, config::Config, trust};
+
+pub fn trust(args_trust: &args::Trust, config: &Config) -> anyhow::Result<()> {
+    let mut paths = args_trust.paths.clone();
+    if paths.is_empty() {
+        paths.push(std::env::current_dir()?);
+    }
+
+    for path in &paths {
+        let path = path.canonicalize()?;
+        let project_file_path = config.find_project_file(&path).ok_or_else(|| {
+            anyhow::format_err!(
+                "no project configuration file found for path '{}'",
+                path.to_string_lossy()
+            )
+        })?;
+        let content = std::fs::read_to_string(&project_file_path)?;
+        trust::add(&project_file_path, &content)?;
+    }
+
+    Ok(())
+}
@@ -1,33 +1,66 @@
+use std::{
+    num::NonZeroU64,
+    path::{Path, PathBuf},
+};
+
+use ahash::HashMap;
+use anyhow::Context as _;
 use mlua::{Lua, LuaSerdeExt as _};
 use ratatui::{
+    crossterm::cursor::SetCursorStyle,
     layout::Alignment,
-    style::{Color, Style},
+    style::{Color, Modifier, Style},
     symbols::border,
 };
 use serde::Deserialize;
-use tmux_interface::Size;
+use tmux_interface::{PaneSize, Size};
 
-use crate::{APP_NAME, args::Args, deserializers};
+use crate::{
+    APP_NAME,
+    args::Args,
+    deserializers,
+    keymap::{Action, Keymap},
+    trust,
+};
 
 #[derive(Debug, Default)]
 pub struct Config {
-    _lua: Lua,
+    lua: Lua,
     pub session_selector: SessionSelector,
     pub keybinds: Keybinds,
+    pub project_files: ProjectFiles,
+    pub project: Project,
 }
 
 #[derive(Debug, Deserialize)]
 #[serde(default)]
+#[allow(clippy::struct_excessive_bools)]
 pub struct SessionSelector {
     #[serde(deserialize_with = "deserializers::size")]
     pub width: Size,
     #[serde(deserialize_with = "deserializers::size")]
     pub height: Size,
+    pub host: SelectorHost,
     pub scrolloff: usize,
     pub inverted: bool,
+    pub frecency_weight: f64,
+    pub sort: SortMode,
+    pub live_switch: LiveSwitch,
+    pub live_switch_delay: u64,
+    pub mouse: bool,
+    pub vi_mode: bool,
+    pub cursor_style: SessionSelectorCursorStyle,
+    pub restore_last_query: bool,
+    pub query_history: SessionSelectorQueryHistory,
     pub paths: SessionSelectorPaths,
+    pub discovery: SessionSelectorDiscovery,
+    pub matching: SessionSelectorMatching,
+    pub browser: SessionSelectorBrowser,
     pub results: SessionSelectorResults,
     pub prompt: SessionSelectorPrompt,
+    pub preview: SessionSelectorPreview,
+    #[serde(skip)]
+    pub keymap: Keymap,
 }
 
 impl Default for SessionSelector {
@@ -35,11 +68,153 @@ impl Default for SessionSelector {
         Self {
             width: Size::Size(48),
             height: Size::Size(16),
+            host: SelectorHost::default(),
             scrolloff: 4,
             inverted: false,
+            frecency_weight: 8.0,
+            sort: SortMode::default(),
+            live_switch: LiveSwitch::default(),
+            live_switch_delay: 200,
+            mouse: true,
+            vi_mode: false,
+            cursor_style: SessionSelectorCursorStyle::default(),
+            restore_last_query: false,
+            query_history: SessionSelectorQueryHistory::default(),
             paths: SessionSelectorPaths::default(),
+            discovery: SessionSelectorDiscovery::default(),
+            matching: SessionSelectorMatching::default(),
+            browser: SessionSelectorBrowser::default(),
             results: SessionSelectorResults::default(),
             prompt: SessionSelectorPrompt::default(),
+            preview: SessionSelectorPreview::default(),
+            keymap: Keymap::default(),
+        }
+    }
+}
+
+/// The order of the results, while the pattern is empty.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SortMode {
+    #[default]
+    Frecency,
+    Path,
+    LastAttached,
+    Activity,
+    Created,
+}
+
+impl SortMode {
+    pub fn next(self) -> Self {
+        match self {
+            Self::Frecency => Self::Path,
+            Self::Path => Self::LastAttached,
+            Self::LastAttached => Self::Activity,
+            Self::Activity => Self::Created,
+            Self::Created => Self::Frecency,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Frecency => "frecency",
+            Self::Path => "path",
+            Self::LastAttached => "last_attached",
+            Self::Activity => "activity",
+            Self::Created => "created",
+        }
+    }
+}
+
+/// Where the key bindings open the session selector.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SelectorHost {
+    /// A popup, which requires tmux 3.2 and otherwise falls back to a window.
+    #[default]
+    Popup,
+    /// A temporary window.
+    Window,
+    /// A temporary pane with the configured height spanning the full width
+    /// of the current window.
+    Split,
+}
+
+/// When the selected session is switched to, while the session selector is
+/// open.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LiveSwitch {
+    /// Switches to every selected session immediately.
+    #[default]
+    Always,
+    /// Switches to the selected session, once no input has been received for
+    /// the configured delay.
+    Debounced,
+    /// Only switches to the selected session once it has been confirmed.
+    Never,
+}
+
+/// The cursor styles of the prompt per editing mode, where the insert mode is
+/// the only one without the vi mode.
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub struct SessionSelectorCursorStyle {
+    pub insert: CursorStyle,
+    pub normal: CursorStyle,
+}
+
+impl Default for SessionSelectorCursorStyle {
+    fn default() -> Self {
+        Self {
+            insert: CursorStyle::SteadyBar,
+            normal: CursorStyle::SteadyBlock,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CursorStyle {
+    /// The cursor style configured in the terminal.
+    Default,
+    BlinkingBlock,
+    SteadyBlock,
+    BlinkingUnderline,
+    SteadyUnderline,
+    BlinkingBar,
+    SteadyBar,
+}
+
+impl From<CursorStyle> for SetCursorStyle {
+    fn from(cursor_style: CursorStyle) -> Self {
+        match cursor_style {
+            CursorStyle::Default => Self::DefaultUserShape,
+            CursorStyle::BlinkingBlock => Self::BlinkingBlock,
+            CursorStyle::SteadyBlock => Self::SteadyBlock,
+            CursorStyle::BlinkingUnderline => Self::BlinkingUnderScore,
+            CursorStyle::SteadyUnderline => Self::SteadyUnderScore,
+            CursorStyle::BlinkingBar => Self::BlinkingBar,
+            CursorStyle::SteadyBar => Self::SteadyBar,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub struct SessionSelectorQueryHistory {
+    /// The maximum number of queries, which are kept.
+    pub size: usize,
+    /// Whether previous occurrences of a query are removed, when it is used
+    /// again.
+    pub deduplicate: bool,
+}
+
+impl Default for SessionSelectorQueryHistory {
+    fn default() -> Self {
+        Self {
+            size: 100,
+            deduplicate: true,
         }
     }
 }
@@ -62,6 +237,121 @@ impl Default for SessionSelectorPaths {
     }
 }
 
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub struct SessionSelectorDiscovery {
+    #[serde(deserialize_with = "deserializers::paths")]
+    pub roots: Vec<PathBuf>,
+    pub max_depth: usize,
+    pub markers: Vec<String>,
+}
+
+impl Default for SessionSelectorDiscovery {
+    fn default() -> Self {
+        Self {
+            roots: Vec::new(),
+            max_depth: 2,
+            markers: vec![
+                String::from(".git"),
+                String::from("Cargo.toml"),
+                String::from("package.json"),
+            ],
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub struct SessionSelectorMatching {
+    pub mode: MatchingMode,
+    /// The minimum score of fuzzy matches per character of the pattern.
+    pub min_score_per_char: u16,
+    /// The maximum number of typos of fuzzy matches per character of the
+    /// pattern, which is rounded down.
+    pub max_typos_per_char: f64,
+    pub case_sensitivity: CaseSensitivity,
+    pub scope: MatchingScope,
+}
+
+impl Default for SessionSelectorMatching {
+    fn default() -> Self {
+        Self {
+            mode: MatchingMode::default(),
+            min_score_per_char: 6,
+            max_typos_per_char: 0.25,
+            case_sensitivity: CaseSensitivity::default(),
+            scope: MatchingScope::default(),
+        }
+    }
+}
+
+/// How the pattern is matched against the paths of the items.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MatchingMode {
+    #[default]
+    Fuzzy,
+    FuzzyNoTypos,
+    /// Matches the pattern as a substring.
+    Exact,
+    Prefix,
+    Regex,
+}
+
+impl MatchingMode {
+    pub fn next(self) -> Self {
+        match self {
+            Self::Fuzzy => Self::FuzzyNoTypos,
+            Self::FuzzyNoTypos => Self::Exact,
+            Self::Exact => Self::Prefix,
+            Self::Prefix => Self::Regex,
+            Self::Regex => Self::Fuzzy,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Fuzzy => "fuzzy",
+            Self::FuzzyNoTypos => "fuzzy_no_typos",
+            Self::Exact => "exact",
+            Self::Prefix => "prefix",
+            Self::Regex => "regex",
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CaseSensitivity {
+    /// Matches case sensitively, only if the pattern contains uppercase
+    /// characters.
+    #[default]
+    Smart,
+    Sensitive,
+    Insensitive,
+}
+
+/// Which part of the paths of the items the pattern is matched against.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MatchingScope {
+    #[default]
+    Path,
+    Basename,
+}
+
+/// The directory browser, which lists the subdirectories of the pattern, while
+/// it is a path.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct SessionSelectorBrowser {
+    /// Whether hidden directories are listed, even if the last component of
+    /// the path does not start with a dot.
+    pub hidden: bool,
+    /// The names of directories, which are never listed.
+    pub ignore: Vec<String>,
+}
+
 #[derive(Debug, Deserialize)]
 #[serde(default)]
 pub struct SessionSelectorResults {
@@ -81,10 +371,20 @@ pub struct SessionSelectorResults {
     #[serde(deserialize_with = "deserializers::style")]
     pub item_match_style: Style,
     #[serde(deserialize_with = "deserializers::style")]
+    pub item_project_style: Style,
+    #[serde(deserialize_with = "deserializers::style")]
     pub selection_style: Style,
     pub selection_prefix: String,
     #[serde(deserialize_with = "deserializers::style")]
     pub selection_prefix_style: Style,
+    pub marker: String,
+    #[serde(deserialize_with = "deserializers::style")]
+    pub marker_style: Style,
+    pub columns: Vec<ResultsColumn>,
+    #[serde(deserialize_with = "deserializers::style")]
+    pub column_style: Style,
+    #[serde(skip)]
+    pub item_format: Option<mlua::Function>,
 }
 
 impl Default for SessionSelectorResults {
@@ -98,13 +398,30 @@ impl Default for SessionSelectorResults {
             title_style: Style::new(),
             item_style: Style::new(),
             item_match_style: Style::new().fg(Color::Blue),
+            item_project_style: Style::new().add_modifier(Modifier::DIM),
             selection_style: Style::new(),
             selection_prefix: String::from("> "),
             selection_prefix_style: Style::new(),
+            marker: String::from("+ "),
+            marker_style: Style::new().fg(Color::Magenta),
+            columns: Vec::new(),
+            column_style: Style::new().add_modifier(Modifier::DIM),
+            item_format: None,
         }
     }
 }
 
+/// A column of session metadata, which is displayed after the path of the
+/// results.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ResultsColumn {
+    Windows,
+    Attached,
+    GitBranch,
+    Idle,
+}
+
 #[derive(Debug, Deserialize)]
 #[serde(default)]
 pub struct SessionSelectorPrompt {
@@ -124,10 +441,20 @@ pub struct SessionSelectorPrompt {
     pub pattern_prefix: String,
     #[serde(deserialize_with = "deserializers::style")]
     pub pattern_prefix_style: Style,
+    /// The indicator of the insert mode, which precedes the pattern prefix in
+    /// the vi mode.
+    pub insert_mode_indicator: String,
+    /// The indicator of the normal mode, which precedes the pattern prefix in
+    /// the vi mode.
+    pub normal_mode_indicator: String,
+    #[serde(deserialize_with = "deserializers::style")]
+    pub mode_indicator_style: Style,
     #[serde(skip)]
     pub stats_format: Option<mlua::Function>,
     #[serde(deserialize_with = "deserializers::style")]
     pub stats_style: Style,
+    #[serde(deserialize_with = "deserializers::style")]
+    pub error_style: Style,
 }
 
 impl Default for SessionSelectorPrompt {
@@ -142,17 +469,73 @@ impl Default for SessionSelectorPrompt {
             pattern_style: Style::new(),
             pattern_prefix: String::from("> "),
             pattern_prefix_style: Style::new(),
+            insert_mode_indicator: String::from("[I] "),
+            normal_mode_indicator: String::from("[N] "),
+            mode_indicator_style: Style::new().add_modifier(Modifier::DIM),
             stats_format: None,
             stats_style: Style::new(),
+            error_style: Style::new().fg(Color::Red),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub struct SessionSelectorPreview {
+    pub enabled: bool,
+    pub position: PreviewPosition,
+    #[serde(deserialize_with = "deserializers::size")]
+    pub size: Size,
+    /// The interval in milliseconds, after which the active pane of the
+    /// selected session is captured again, which must not be zero, so the
+    /// session selector does not capture the pane continuously.
+    pub refresh_interval: NonZeroU64,
+    #[serde(deserialize_with = "deserializers::style")]
+    pub style: Style,
+    #[serde(deserialize_with = "deserializers::border_set")]
+    pub border: border::Set,
+    #[serde(deserialize_with = "deserializers::style")]
+    pub border_style: Style,
+    pub title: String,
+    #[serde(deserialize_with = "deserializers::alignment")]
+    pub title_alignment: Alignment,
+    #[serde(deserialize_with = "deserializers::style")]
+    pub title_style: Style,
+}
+
+impl Default for SessionSelectorPreview {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            position: PreviewPosition::default(),
+            size: Size::Percentage(50),
+            refresh_interval: NonZeroU64::new(1000).expect("interval should not be zero"),
+            style: Style::new(),
+            border: border::ROUNDED,
+            border_style: Style::new(),
+            title: String::from(" Preview "),
+            title_alignment: Alignment::Center,
+            title_style: Style::new(),
         }
     }
 }
 
+/// The position of the preview relative to the results.
+#[derive(Debug, Default, Clone, Copy, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PreviewPosition {
+    #[default]
+    Right,
+    Bottom,
+}
+
 #[derive(Debug, Deserialize)]
 #[serde(default)]
 pub struct Keybinds {
     pub select_session: Vec<String>,
     pub last_session: Vec<String>,
+    pub history_back: Vec<String>,
+    pub history_forward: Vec<String>,
 }
 
 impl Default for Keybinds {
@@ -160,10 +543,108 @@ impl Default for Keybinds {
         Self {
             select_session: vec![String::from("C-s")],
             last_session: vec![String::from("w")],
+            history_back: vec![String::from("(")],
+            history_forward: vec![String::from(")")],
         }
     }
 }
 
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct ProjectFiles {
+    pub enabled: bool,
+    pub file_name: String,
+    pub search_ancestors: bool,
+}
+
+impl Default for ProjectFiles {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            file_name: format!(".{APP_NAME}.lua"),
+            search_ancestors: false,
+        }
+    }
+}
+
+/// Options for the sessions created by tmuxion, which project configuration
+/// files may override for their directory.
+#[derive(Debug, Default, Clone)]
+pub struct Project {
+    pub name: Option<SessionName>,
+    pub environment: HashMap<String, String>,
+    pub layout: Layout,
+}
+
+impl Project {
+    /// Returns the unsanitized name for a session at the given path, which
+    /// defaults to the path's basename.
+    pub fn session_name(&self, path: &Path) -> anyhow::Result<String> {
+        let name = match &self.name {
+            Some(SessionName::Static(name)) => name.clone(),
+            Some(SessionName::Function(name_fn)) => name_fn
+                .call::<String>(path.to_string_lossy())
+                .context("failed to call session name function")?,
+            None => path
+                .file_name()
+                .map(|file_name| file_name.to_string_lossy().into_owned())
+                .unwrap_or_default(),
+        };
+        Ok(name)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum SessionName {
+    Static(String),
+    Function(mlua::Function),
+}
+
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(default)]
+pub struct Layout {
+    pub windows: Vec<LayoutWindow>,
+}
+
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(default)]
+pub struct LayoutWindow {
+    pub name: Option<String>,
+    pub path: Option<PathBuf>,
+    pub layout: Option<String>,
+    pub focus: bool,
+    pub panes: Vec<LayoutPane>,
+}
+
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(default)]
+pub struct LayoutPane {
+    pub split: LayoutSplit,
+    #[serde(deserialize_with = "deserializers::pane_size")]
+    pub size: Option<PaneSize>,
+    pub path: Option<PathBuf>,
+    pub command: Option<String>,
+    pub focus: bool,
+}
+
+#[derive(Debug, Default, Clone, Copy, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LayoutSplit {
+    Horizontal,
+    #[default]
+    Vertical,
+}
+
+#[derive(Default)]
+struct Registrations {
+    session_selector: Option<SessionSelector>,
+    keybinds: Option<Keybinds>,
+    project_files: Option<ProjectFiles>,
+    session_name: Option<SessionName>,
+    environment: Option<HashMap<String, String>>,
+    layout: Option<Layout>,
+}
+
 impl Config {
     pub fn new(args: &Args) -> anyhow::Result<Self> {
         let path = args.config_file.clone().unwrap_or_else(|| {
@@ -172,7 +653,7 @@ impl Config {
                 .join(APP_NAME)
                 .join("config.lua")
         });
-        let code = match std::fs::read_to_string(path) {
+        let code = match std::fs::read_to_string(&path) {
             Ok(content) => content,
             Err(err) => {
                 if err.kind() == std::io::ErrorKind::NotFound && args.config_file.is_none() {
@@ -183,52 +664,162 @@ impl Config {
         };
 
         let lua = Lua::new();
-        let globals = lua.globals();
-        let package = globals.get::<mlua::Table>("package")?;
-        let loaded = package.get::<mlua::Table>("loaded")?;
-        let module = match loaded.get(APP_NAME)? {
-            mlua::Value::Table(module) => anyhow::Ok(module),
-            mlua::Value::Nil => {
-                let module = lua.create_table()?;
-                loaded.set(APP_NAME, module.clone())?;
-                anyhow::Ok(module)
-            }
-            other => anyhow::bail!(
-                "failed to register '{APP_NAME}' module: 'package.loaded.{APP_NAME}' is already set to a value of type {}",
-                other.type_name()
-            ),
-        }?;
-
-        let deserialize_opts = mlua::DeserializeOptions::default().deny_unsupported_types(false);
-
-        let mut session_selector_opt = None;
-        let mut keybinds_opt = None;
-        lua.scope(|scope| {
-            let session_selector_fn = scope.create_function_mut(|lua, v: mlua::Value| {
-                let mut session_selector =
-                    lua.from_value_with::<SessionSelector>(v.clone(), deserialize_opts)?;
-                session_selector.prompt.stats_format =
-                    get_session_selector_prompt_stats_format(lua, &v)?;
-                session_selector_opt = Some(session_selector);
-                Ok(())
-            })?;
-            module.set("session_selector", session_selector_fn)?;
-
-            let keybinds_fn = scope.create_function_mut(|lua, v: mlua::Value| {
-                keybinds_opt = Some(lua.from_value_with(v, deserialize_opts)?);
-                Ok(())
-            })?;
-            module.set("keybinds", keybinds_fn)?;
-
-            lua.load(code).exec()
-        })?;
+        let registrations = exec(&lua, &code, &path)?;
 
         Ok(Self {
-            _lua: lua,
-            session_selector: session_selector_opt.unwrap_or_default(),
-            keybinds: keybinds_opt.unwrap_or_default(),
+            lua,
+            session_selector: registrations.session_selector.unwrap_or_default(),
+            keybinds: registrations.keybinds.unwrap_or_default(),
+            project_files: registrations.project_files.unwrap_or_default(),
+            project: Project {
+                name: registrations.session_name,
+                environment: registrations.environment.unwrap_or_default(),
+                layout: registrations.layout.unwrap_or_default(),
+            },
         })
     }
+
+    /// Returns the project options for the given directory, overridden by its
+    /// project configuration file, if there is one and it is trusted.
+    pub fn project(&self, path: &Path) -> anyhow::Result<Project> {
+        let mut project = self.project.clone();
+        if !self.project_files.enabled {
+            return Ok(project);
+        }
+        let Some(project_file_path) = self.find_project_file(path) else {
+            return Ok(project);
+        };
+        let code = std::fs::read_to_string(&project_file_path)?;
+        if !trust::check(&project_file_path, &code)? {
+            return Ok(project);
+        }
+
+        let registrations = exec(&self.lua, &code, &project_file_path).with_context(|| {
+            format!(
+                "failed to parse project configuration file '{}'",
+                project_file_path.to_string_lossy()
+            )
+        })?;
+        if let Some(session_name) = registrations.session_name {
+            project.name = Some(session_name);
+        }
+        if let Some(environment) = registrations.environment {
+            project.environment.extend(environment);
+        }
+        if let Some(layout) = registrations.layout {
+            project.layout = layout;
+        }
+        Ok(project)
+    }
+
+    pub fn find_project_file(&self, path: &Path) -> Option<PathBuf> {
+        let ancestors_count = if self.project_files.search_ancestors {
+            usize::MAX
+        } else {
+            1
+        };
+        path.ancestors()
+            .take(ancestors_count)
+            .map(|dir| dir.join(&self.project_files.file_name))
+            .find(|project_file_path| project_file_path.is_file())
+    }
+}
+
+fn exec(lua: &Lua, code: &str, path: &Path) -> anyhow::Result<Registrations> {
+    let globals = lua.globals();
+    let package = globals.get::<mlua::Table>("package")?;
+    let loaded = package.get::<mlua::Table>("loaded")?;
+    let module = match loaded.get(APP_NAME)? {
+        mlua::Value::Table(module) => anyhow::Ok(module),
+        mlua::Value::Nil => {
+            let module = lua.create_table()?;
+            loaded.set(APP_NAME, module.clone())?;
+            anyhow::Ok(module)
+        }
+        other => anyhow::bail!(
+            "failed to register '{APP_NAME}' module: 'package.loaded.{APP_NAME}' is already set to a value of type {}",
+            other.type_name()
+        ),
+    }?;
+
+    let deserialize_opts = mlua::DeserializeOptions::default().deny_unsupported_types(false);
+
+    let mut registrations = Registrations::default();
+    lua.scope(|scope| {
+        let session_selector_fn = scope.create_function_mut(|lua, v: mlua::Value| {
+            let mut session_selector =
+                lua.from_value_with::<SessionSelector>(v.clone(), deserialize_opts)?;
+            session_selector.results.item_format =
+                get_session_selector_results_item_format(lua, &v)?;
+            session_selector.prompt.stats_format =
+                get_session_selector_prompt_stats_format(lua, &v)?;
+            session_selector.keymap = get_session_selector_keymap(lua, &v)?;
+            registrations.session_selector = Some(session_selector);
+            Ok(())
+        })?;
+        module.set("session_selector", session_selector_fn)?;
+
+        let keybinds_fn = scope.create_function_mut(|lua, v: mlua::Value| {
+            registrations.keybinds = Some(lua.from_value_with(v, deserialize_opts)?);
+            Ok(())
+        })?;
+        module.set("keybinds", keybinds_fn)?;
+
+        let project_files_fn = scope.create_function_mut(|lua, v: mlua::Value| {
+            registrations.project_files = Some(lua.from_value_with(v, deserialize_opts)?);
+            Ok(())
+        })?;
+        module.set("project_files", project_files_fn)?;
+
+        let session_name_fn = scope.create_function_mut(|_, v: mlua::Value| {
+            let session_name = match v {
+                mlua::Value::String(name) => SessionName::Static(name.to_str()?.to_string()),
+                mlua::Value::Function(name_fn) => SessionName::Function(name_fn),
+                other => {
+                    return Err(mlua::Error::runtime(format!(
+                        "expected a string or a function, got a value of type {}",
+                        other.type_name()
+                    )));
+                }
+            };
+            registrations.session_name = Some(session_name);
+            Ok(())
+        })?;
+        module.set("session_name", session_name_fn)?;
+
+        let environment_fn = scope.create_function_mut(|lua, v: mlua::Value| {
+            registrations.environment = Some(lua.from_value_with(v, deserialize_opts)?);
+            Ok(())
+        })?;
+        module.set("environment", environment_fn)?;
+
+        let layout_fn = scope.create_function_mut(|lua, v: mlua::Value| {
+            registrations.layout = Some(lua.from_value_with(v, deserialize_opts)?);
+            Ok(())
+        })?;
+        module.set("layout", layout_fn)?;
+
+        lua.load(code)
+            .set_name(format!("@{}", path.to_string_lossy()))
+            .exec()
+    })?;
+
+    Ok(registrations)
+}
+
+fn get_session_selector_results_item_format(
+    lua: &Lua,
+    v: &mlua::Value,
+) -> mlua::Result<Option<mlua::Function>> {
+    let Some(session_selector_table) = lua.convert::<Option<mlua::Table>>(v)? else {
+        return Ok(None);
+    };
+    let Some(session_selector_results_table) =
+        session_selector_table.get::<Option<mlua::Table>>("results")?
+    else {
+        return Ok(None);
+    };
+    session_selector_results_table.get("item_format")
 }
 
 fn get_session_selector_prompt_stats_format(
@@ -245,3 +836,47 @@ fn get_session_selector_prompt_stats_format(
     };
     session_selector_prompt_table.get("stats_format")
 }
+
+/// Returns the default keymap overridden by the bindings of the keymap table,
+/// which maps keys to either names of actions, Lua functions or `"none"` to
+/// unbind the key.
+fn get_session_selector_keymap(lua: &Lua, v: &mlua::Value) -> mlua::Result<Keymap> {
+    let mut keymap = Keymap::default();
+    let Some(session_selector_table) = lua.convert::<Option<mlua::Table>>(v)? else {
+        return Ok(keymap);
+    };
+    let Some(keymap_table) = session_selector_table.get::<Option<mlua::Table>>("keymap")? else {
+        return Ok(keymap);
+    };
+
+    // Different key strings may describe the same key, e.g. `C-j` and
+    // `ctrl-j`, which would make the resulting binding depend on the
+    // iteration order of the table.
+    let mut bound_key_strs = HashMap::default();
+    for pair in keymap_table.pairs::<String, mlua::Value>() {
+        let (key_str, v) = pair?;
+        let key = match v {
+            mlua::Value::String(action_str) if action_str.to_str()? == "none" => {
+                keymap.unbind(&key_str)
+            }
+            mlua::Value::String(action_str) => action_str
+                .to_str()?
+                .parse::<Action>()
+                .and_then(|action| keymap.bind(&key_str, action)),
+            mlua::Value::Function(action_fn) => keymap.bind(&key_str, Action::Function(action_fn)),
+            other => {
+                return Err(mlua::Error::runtime(format!(
+                    "expected a string or a function for key '{key_str}', got a value of type {}",
+                    other.type_name()
+                )));
+            }
+        }
+        .map_err(|err| mlua::Error::runtime(format!("{err:#}")))?;
+        if let Some(bound_key_str) = bound_key_strs.insert(key, key_str.clone()) {
+            return Err(mlua::Error::runtime(format!(
+                "conflicting key bindings '{bound_key_str}' and '{key_str}'"
+            )));
+        }
+    }
+    Ok(keymap)
+}
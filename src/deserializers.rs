@@ -1,10 +1,14 @@
+use std::path::PathBuf;
+
 use ratatui::{
     layout::Alignment,
     style::{Color, Modifier, Style},
     symbols::border,
 };
 use serde::{Deserialize, Deserializer, de::Visitor};
-use tmux_interface::Size;
+use tmux_interface::{PaneSize, Size};
+
+use crate::path;
 
 const ALIGNMENT_STRING_VALUES: &[&str] = &["left", "center", "right"];
 
@@ -65,6 +69,30 @@ where
     deserializer.deserialize_any(SizeVisitor)
 }
 
+pub fn pane_size<'de, D>(deserializer: D) -> Result<Option<PaneSize>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let pane_size = match size(deserializer)? {
+        Size::Size(v) => PaneSize::Size(v),
+        Size::Percentage(v) => PaneSize::Percentage(v),
+    };
+    Ok(Some(pane_size))
+}
+
+/// Deserializes a sequence of paths, expanding a leading `~` to the home
+/// directory.
+pub fn paths<'de, D>(deserializer: D) -> Result<Vec<PathBuf>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let paths = Vec::<String>::deserialize(deserializer)?
+        .into_iter()
+        .map(|path| path::expand_home_dir(&path))
+        .collect();
+    Ok(paths)
+}
+
 const BORDER_SET_STRING_VALUES: &[&str] = &[
     "plain",
     "rounded",
@@ -0,0 +1,51 @@
+use std::{collections::VecDeque, path::PathBuf};
+
+use ahash::HashSet;
+
+use crate::config::SessionSelectorDiscovery;
+
+/// Returns the sorted canonical paths of all directories below the configured
+/// roots, which contain at least one of the configured marker files.
+///
+/// Hidden directories and the subdirectories of discovered projects are not
+/// searched.
+pub fn discover(discovery: &SessionSelectorDiscovery) -> Vec<PathBuf> {
+    let mut projects = HashSet::default();
+    for root in &discovery.roots {
+        let Ok(root) = root.canonicalize() else {
+            continue;
+        };
+        let mut dirs = VecDeque::from([(root, 0)]);
+        while let Some((dir, depth)) = dirs.pop_front() {
+            if discovery
+                .markers
+                .iter()
+                .any(|marker| dir.join(marker).exists())
+            {
+                projects.insert(dir);
+                continue;
+            }
+            if depth >= discovery.max_depth {
+                continue;
+            }
+            let Ok(entries) = std::fs::read_dir(&dir) else {
+                continue;
+            };
+            for entry in entries.flatten() {
+                if entry.file_name().to_string_lossy().starts_with('.') {
+                    continue;
+                }
+                let path = entry.path();
+                if !path.is_dir() {
+                    continue;
+                }
+                if let Ok(path) = path.canonicalize() {
+                    dirs.push_back((path, depth + 1));
+                }
+            }
+        }
+    }
+    let mut projects = projects.into_iter().collect::<Vec<_>>();
+    projects.sort();
+    projects
+}
@@ -0,0 +1,97 @@
+use std::{
+    path::{Path, PathBuf},
+    sync::LazyLock,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use ahash::HashMap;
+use serde::{Deserialize, Serialize};
+
+use crate::{APP_NAME, json_store};
+
+const HOUR: u64 = 60 * 60;
+const DAY: u64 = 24 * HOUR;
+const WEEK: u64 = 7 * DAY;
+/// The sum of all ranks, above which all ranks are aged, so that entries,
+/// which have not been accessed for a long time, are eventually removed.
+const RANK_SUM_MAX: f64 = 10_000.0;
+static FRECENCY_FILE_PATH: LazyLock<PathBuf> = LazyLock::new(|| {
+    dirs::data_dir()
+        .unwrap_or_default()
+        .join(APP_NAME)
+        .join("frecency.json")
+});
+
+/// A frecency store for directory paths, which ranks paths by how frequently
+/// and how recently they have been accessed, similar to zoxide.
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(transparent)]
+pub struct Frecency {
+    entries: HashMap<PathBuf, Entry>,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize, Serialize)]
+struct Entry {
+    rank: f64,
+    last_accessed: u64,
+}
+
+impl Frecency {
+    pub fn read() -> anyhow::Result<Self> {
+        json_store::read(&FRECENCY_FILE_PATH)
+    }
+
+    pub fn write(&self) -> anyhow::Result<()> {
+        json_store::write(&FRECENCY_FILE_PATH, self)
+    }
+
+    pub fn score(&self, path: &Path) -> f64 {
+        let Some(entry) = self.entries.get(path) else {
+            return 0.0;
+        };
+        let duration = now().saturating_sub(entry.last_accessed);
+        let factor = if duration < HOUR {
+            4.0
+        } else if duration < DAY {
+            2.0
+        } else if duration < WEEK {
+            0.5
+        } else {
+            0.25
+        };
+        entry.rank * factor
+    }
+
+    pub fn add(&mut self, path: &Path, rank: f64) {
+        let now = now();
+        let entry = self.entries.entry(path.to_path_buf()).or_insert(Entry {
+            rank: 0.0,
+            last_accessed: now,
+        });
+        entry.rank += rank;
+        entry.last_accessed = now;
+
+        let rank_sum = self.entries.values().map(|entry| entry.rank).sum::<f64>();
+        if rank_sum > RANK_SUM_MAX {
+            let factor = 0.9 * RANK_SUM_MAX / rank_sum;
+            for entry in self.entries.values_mut() {
+                entry.rank *= factor;
+            }
+            self.entries.retain(|_, entry| entry.rank >= 1.0);
+        }
+    }
+}
+
+/// Records an access of the given path in the frecency store.
+pub fn record(path: &Path) -> anyhow::Result<()> {
+    let mut frecency = Frecency::read()?;
+    frecency.add(path, 1.0);
+    frecency.write()
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or_default()
+}
@@ -0,0 +1,29 @@
+use std::path::{Path, PathBuf};
+
+/// Returns the checked out branch of the git repository containing the path
+/// or the abbreviated commit hash, if the `HEAD` is detached.
+pub fn branch(path: &Path) -> Option<String> {
+    let git_path = path
+        .ancestors()
+        .map(|dir| dir.join(".git"))
+        .find(|git_path| git_path.exists())?;
+    // The `.git` file of worktrees and submodules points to the actual git
+    // directory.
+    let git_dir = if git_path.is_file() {
+        let content = std::fs::read_to_string(&git_path).ok()?;
+        let git_dir = PathBuf::from(content.strip_prefix("gitdir:")?.trim());
+        git_path.parent()?.join(git_dir)
+    } else {
+        git_path
+    };
+    let head = std::fs::read_to_string(git_dir.join("HEAD")).ok()?;
+    let head = head.trim();
+    let branch = match head.strip_prefix("ref: ") {
+        Some(reference) => reference
+            .strip_prefix("refs/heads/")
+            .unwrap_or(reference)
+            .to_string(),
+        None => head.chars().take(7).collect(),
+    };
+    Some(branch)
+}
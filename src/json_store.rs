@@ -0,0 +1,66 @@
+use std::path::Path;
+
+use serde::{Serialize, de::DeserializeOwned};
+
+/// Reads the value from the JSON file at the path, which is the default value,
+/// if the file does not exist or is corrupt, so a file written only partially
+/// does not prevent any further commands.
+pub fn read<T>(path: &Path) -> anyhow::Result<T>
+where
+    T: DeserializeOwned + Default,
+{
+    let content = match std::fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(T::default()),
+        Err(err) => anyhow::bail!(err),
+    };
+    Ok(serde_json::from_str(&content).unwrap_or_default())
+}
+
+/// Writes the value to the JSON file at the path, creating its parent
+/// directories. The file is replaced at once, so concurrent commands never
+/// read it written only partially.
+pub fn write<T>(path: &Path, value: &T) -> anyhow::Result<()>
+where
+    T: Serialize,
+{
+    if let Some(path_parent) = path.parent() {
+        std::fs::create_dir_all(path_parent)?;
+    }
+    let mut temp_path = path.as_os_str().to_owned();
+    temp_path.push(format!(".{}.tmp", std::process::id()));
+    std::fs::write(&temp_path, serde_json::to_string(value)?)?;
+    std::fs::rename(&temp_path, path)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+
+    fn temp_file_path(name: &str) -> PathBuf {
+        std::env::temp_dir()
+            .join(format!("tmuxion-json-store-{}", std::process::id()))
+            .join(name)
+    }
+
+    #[test]
+    fn missing_and_corrupt_files_are_read_as_default() -> anyhow::Result<()> {
+        let path = temp_file_path("corrupt.json");
+        assert_eq!(read::<Vec<String>>(&path)?, Vec::<String>::new());
+        write(&path, &vec!["foo".to_owned()])?;
+        std::fs::write(&path, "[\"fo")?;
+        assert_eq!(read::<Vec<String>>(&path)?, Vec::<String>::new());
+        Ok(())
+    }
+
+    #[test]
+    fn written_values_are_read_back() -> anyhow::Result<()> {
+        let path = temp_file_path("written.json");
+        write(&path, &vec!["foo".to_owned(), "bar".to_owned()])?;
+        assert_eq!(read::<Vec<String>>(&path)?, vec!["foo", "bar"]);
+        Ok(())
+    }
+}
@@ -0,0 +1,372 @@
+use std::str::FromStr;
+
+use ahash::HashMap;
+use ratatui::crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+const ACTION_STRING_VALUES: &[&str] = &[
+    "select_next",
+    "select_prev",
+    "select_up",
+    "select_down",
+    "select_up_or_recall_query",
+    "cursor_left",
+    "cursor_right",
+    "cursor_start",
+    "cursor_end",
+    "cursor_word_left",
+    "cursor_word_right",
+    "delete_backward",
+    "delete_forward",
+    "delete_word",
+    "delete_word_forward",
+    "delete_to_start",
+    "delete_to_end",
+    "yank",
+    "toggle_mark",
+    "toggle_mark_prev",
+    "cycle_sort",
+    "cycle_matching_mode",
+    "recall_query",
+    "complete_path",
+    "toggle_browse",
+    "confirm",
+    "abort",
+    "kill_session",
+    "detach_other_clients",
+    "send_keys",
+];
+const DEFAULT_BINDINGS: &[(&str, &str)] = &[
+    ("down", "select_down"),
+    ("ctrl-j", "select_down"),
+    ("ctrl-n", "select_down"),
+    ("up", "select_up_or_recall_query"),
+    ("ctrl-p", "select_up"),
+    ("left", "cursor_left"),
+    ("ctrl-h", "cursor_left"),
+    ("right", "cursor_right"),
+    ("ctrl-l", "cursor_right"),
+    ("ctrl-b", "cursor_left"),
+    ("ctrl-f", "cursor_right"),
+    ("alt-b", "cursor_word_left"),
+    ("alt-f", "cursor_word_right"),
+    ("home", "cursor_start"),
+    ("ctrl-a", "cursor_start"),
+    ("end", "cursor_end"),
+    ("ctrl-e", "cursor_end"),
+    ("backspace", "delete_backward"),
+    ("delete", "delete_forward"),
+    ("ctrl-w", "delete_word"),
+    ("alt-d", "delete_word_forward"),
+    ("ctrl-u", "delete_to_start"),
+    ("ctrl-k", "delete_to_end"),
+    ("ctrl-y", "yank"),
+    ("tab", "toggle_mark"),
+    ("backtab", "toggle_mark_prev"),
+    ("ctrl-t", "cycle_sort"),
+    ("alt-m", "cycle_matching_mode"),
+    ("ctrl-r", "recall_query"),
+    ("ctrl-space", "complete_path"),
+    ("ctrl-o", "toggle_browse"),
+    ("enter", "confirm"),
+    ("esc", "abort"),
+    ("ctrl-c", "abort"),
+    ("ctrl-x", "kill_session"),
+    ("ctrl-d", "detach_other_clients"),
+    ("ctrl-s", "send_keys"),
+];
+
+/// An action of the session selector, which can be bound to a key.
+#[derive(Debug, Clone)]
+pub enum Action {
+    /// Selects the next, i.e. lower ranked, result.
+    SelectNext,
+    /// Selects the previous, i.e. higher ranked, result.
+    SelectPrev,
+    /// Selects the result above the selected one.
+    SelectUp,
+    /// Selects the result below the selected one.
+    SelectDown,
+    /// Recalls the previous query, while the pattern is empty or has been
+    /// recalled, and otherwise selects the result above the selected one.
+    SelectUpOrRecallQuery,
+    CursorLeft,
+    CursorRight,
+    CursorStart,
+    CursorEnd,
+    /// Moves the cursor to the start of the current or previous word.
+    CursorWordLeft,
+    /// Moves the cursor to the end of the current or next word.
+    CursorWordRight,
+    DeleteBackward,
+    DeleteForward,
+    /// Deletes the whitespace delimited word before the cursor.
+    DeleteWord,
+    /// Deletes the text up to the end of the current or next word.
+    DeleteWordForward,
+    DeleteToStart,
+    DeleteToEnd,
+    /// Inserts the last deleted word or line part at the cursor.
+    Yank,
+    /// Toggles the mark of the selected session and selects the next result,
+    /// or completes the path, while browsing or while a path is typed.
+    ToggleMark,
+    /// Toggles the mark of the selected session and selects the previous
+    /// result.
+    ToggleMarkPrev,
+    /// Switches to the next sort mode of the results.
+    CycleSort,
+    /// Switches to the next matching mode of the pattern.
+    CycleMatchingMode,
+    /// Replaces the pattern with the previous query from the query history.
+    RecallQuery,
+    /// Completes the last component of the path, which has been typed as
+    /// pattern, from the filesystem.
+    CompletePath,
+    /// Switches between matching the sessions and projects and browsing the
+    /// subdirectories of the pattern as a path.
+    ToggleBrowse,
+    Confirm,
+    Abort,
+    KillSession,
+    DetachOtherClients,
+    /// Sends a command, which is entered in the prompt, to the active panes of
+    /// the sessions.
+    SendKeys,
+    /// A Lua function, which is called with the pattern, the path of the
+    /// selected item and the name of the selected session and may return the
+    /// name of an action to perform afterwards.
+    Function(mlua::Function),
+}
+
+impl FromStr for Action {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let action = match s {
+            "select_next" => Self::SelectNext,
+            "select_prev" => Self::SelectPrev,
+            "select_up" => Self::SelectUp,
+            "select_down" => Self::SelectDown,
+            "select_up_or_recall_query" => Self::SelectUpOrRecallQuery,
+            "cursor_left" => Self::CursorLeft,
+            "cursor_right" => Self::CursorRight,
+            "cursor_start" => Self::CursorStart,
+            "cursor_end" => Self::CursorEnd,
+            "cursor_word_left" => Self::CursorWordLeft,
+            "cursor_word_right" => Self::CursorWordRight,
+            "delete_backward" => Self::DeleteBackward,
+            "delete_forward" => Self::DeleteForward,
+            "delete_word" => Self::DeleteWord,
+            "delete_word_forward" => Self::DeleteWordForward,
+            "delete_to_start" => Self::DeleteToStart,
+            "delete_to_end" => Self::DeleteToEnd,
+            "yank" => Self::Yank,
+            "toggle_mark" => Self::ToggleMark,
+            "toggle_mark_prev" => Self::ToggleMarkPrev,
+            "cycle_sort" => Self::CycleSort,
+            "cycle_matching_mode" => Self::CycleMatchingMode,
+            "recall_query" => Self::RecallQuery,
+            "complete_path" => Self::CompletePath,
+            "toggle_browse" => Self::ToggleBrowse,
+            "confirm" => Self::Confirm,
+            "abort" => Self::Abort,
+            "kill_session" => Self::KillSession,
+            "detach_other_clients" => Self::DetachOtherClients,
+            "send_keys" => Self::SendKeys,
+            s => anyhow::bail!(
+                "unknown action '{s}', expected one of {}",
+                ACTION_STRING_VALUES.join(", ")
+            ),
+        };
+        Ok(action)
+    }
+}
+
+/// A key combined with modifiers, e.g. `ctrl-j`, `alt-b` or `shift-tab`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Key {
+    modifiers: KeyModifiers,
+    code: KeyCode,
+}
+
+impl Key {
+    /// Normalizes the key, so equivalent key combinations are equal, as the
+    /// shift modifier is already contained in characters and the back tab.
+    fn new(mut modifiers: KeyModifiers, mut code: KeyCode) -> Self {
+        if modifiers.contains(KeyModifiers::SHIFT) {
+            match code {
+                KeyCode::Char(c) => {
+                    code = KeyCode::Char(c.to_ascii_uppercase());
+                    modifiers.remove(KeyModifiers::SHIFT);
+                }
+                KeyCode::Tab | KeyCode::BackTab => {
+                    code = KeyCode::BackTab;
+                    modifiers.remove(KeyModifiers::SHIFT);
+                }
+                _ => (),
+            }
+        }
+        Self { modifiers, code }
+    }
+}
+
+impl From<&KeyEvent> for Key {
+    fn from(event: &KeyEvent) -> Self {
+        Self::new(event.modifiers, event.code)
+    }
+}
+
+impl FromStr for Key {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (modifiers_str, code_str) = if s == "-" {
+            ("", s)
+        } else if let Some(modifiers_str) = s.strip_suffix("--") {
+            (modifiers_str, "-")
+        } else {
+            s.rsplit_once('-').unwrap_or(("", s))
+        };
+
+        let mut modifiers = KeyModifiers::NONE;
+        for modifier_str in modifiers_str.split('-').filter(|m| !m.is_empty()) {
+            modifiers |= match modifier_str.to_lowercase().as_str() {
+                "c" | "ctrl" | "control" => KeyModifiers::CONTROL,
+                "a" | "m" | "alt" | "meta" => KeyModifiers::ALT,
+                "s" | "shift" => KeyModifiers::SHIFT,
+                _ => anyhow::bail!("unknown modifier '{modifier_str}' in key '{s}'"),
+            };
+        }
+
+        let mut chars = code_str.chars();
+        let code = match (chars.next(), chars.next()) {
+            (Some(c), None) => KeyCode::Char(c),
+            _ => match code_str.to_lowercase().as_str() {
+                "space" => KeyCode::Char(' '),
+                "enter" | "return" | "cr" => KeyCode::Enter,
+                "esc" | "escape" => KeyCode::Esc,
+                "tab" => KeyCode::Tab,
+                "backtab" => KeyCode::BackTab,
+                "backspace" | "bs" => KeyCode::Backspace,
+                "delete" | "del" => KeyCode::Delete,
+                "insert" | "ins" => KeyCode::Insert,
+                "home" => KeyCode::Home,
+                "end" => KeyCode::End,
+                "pageup" => KeyCode::PageUp,
+                "pagedown" => KeyCode::PageDown,
+                "up" => KeyCode::Up,
+                "down" => KeyCode::Down,
+                "left" => KeyCode::Left,
+                "right" => KeyCode::Right,
+                code_str => code_str
+                    .strip_prefix('f')
+                    .and_then(|n| n.parse::<u8>().ok())
+                    .map(KeyCode::F)
+                    .ok_or(anyhow::format_err!("unknown key '{s}'"))?,
+            },
+        };
+
+        Ok(Self::new(modifiers, code))
+    }
+}
+
+/// The mapping of keys to the actions of the session selector.
+#[derive(Debug)]
+pub struct Keymap(HashMap<Key, Action>);
+
+impl Keymap {
+    pub fn get(&self, event: &KeyEvent) -> Option<&Action> {
+        self.0.get(&Key::from(event))
+    }
+
+    /// Binds the key to the action, overriding its default binding, and returns
+    /// the key.
+    pub fn bind(&mut self, key_str: &str, action: Action) -> anyhow::Result<Key> {
+        let key = key_str.parse::<Key>()?;
+        self.0.insert(key, action);
+        Ok(key)
+    }
+
+    /// Unbinds the key and returns it.
+    pub fn unbind(&mut self, key_str: &str) -> anyhow::Result<Key> {
+        let key = key_str.parse::<Key>()?;
+        self.0.remove(&key);
+        Ok(key)
+    }
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        let mut keymap = Self(HashMap::default());
+        for (key_str, action_str) in DEFAULT_BINDINGS {
+            keymap
+                .bind(
+                    key_str,
+                    action_str.parse().expect("default action should be valid"),
+                )
+                .expect("default key should be valid");
+        }
+        keymap
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(key_str: &str) -> Key {
+        key_str.parse().expect("key should be valid")
+    }
+
+    #[test]
+    fn dash_is_a_key_and_a_separator() {
+        assert_eq!(key("-"), Key::new(KeyModifiers::NONE, KeyCode::Char('-')));
+        assert_eq!(
+            key("ctrl--"),
+            Key::new(KeyModifiers::CONTROL, KeyCode::Char('-'))
+        );
+        assert_eq!(
+            key("ctrl-alt--"),
+            Key::new(
+                KeyModifiers::CONTROL | KeyModifiers::ALT,
+                KeyCode::Char('-')
+            )
+        );
+    }
+
+    #[test]
+    fn modifier_aliases_are_equal() {
+        assert_eq!(key("C-j"), key("ctrl-j"));
+        assert_eq!(key("control-j"), key("ctrl-j"));
+        assert_eq!(key("M-b"), key("alt-b"));
+        assert_eq!(key("Ctrl-Alt-x"), key("c-a-x"));
+    }
+
+    #[test]
+    fn shift_tab_is_back_tab() {
+        assert_eq!(key("shift-tab"), key("backtab"));
+        assert_eq!(key("s-backtab"), key("backtab"));
+        assert_eq!(
+            Key::from(&KeyEvent::new(KeyCode::BackTab, KeyModifiers::SHIFT)),
+            key("shift-tab")
+        );
+    }
+
+    #[test]
+    fn shift_is_contained_in_uppercase_characters() {
+        assert_eq!(key("shift-a"), key("A"));
+        assert_eq!(key("ctrl-shift-a"), key("ctrl-A"));
+        assert_eq!(
+            Key::from(&KeyEvent::new(KeyCode::Char('A'), KeyModifiers::SHIFT)),
+            key("A")
+        );
+        assert_ne!(key("a"), key("A"));
+    }
+
+    #[test]
+    fn unknown_keys_and_modifiers_are_rejected() {
+        assert!("ctrl-foo".parse::<Key>().is_err());
+        assert!("hyper-a".parse::<Key>().is_err());
+        assert!("".parse::<Key>().is_err());
+    }
+}
@@ -2,7 +2,16 @@ mod args;
 mod command;
 mod config;
 mod deserializers;
+mod discovery;
+mod frecency;
+mod git;
+mod json_store;
+mod keymap;
+mod matching;
+mod path;
+mod query_history;
 mod tmux;
+mod trust;
 
 use anyhow::Context as _;
 use ratatui::crossterm::style::Stylize as _;
@@ -26,7 +35,13 @@ fn run(args: &Args) -> anyhow::Result<()> {
     let config = Config::new(args).context("failed to parse configuration file")?;
     match &args.command {
         Command::Create(args_create) => command::create(args, args_create, &config),
-        Command::Select => command::select(args, &config),
+        Command::Kill(args_kill) => command::kill(args, args_kill),
+        Command::List(args_list) => command::list(args_list, &config),
+        Command::Select(args_select) => command::select(args, args_select, &config),
         Command::Last => command::last(args),
+        Command::Back => command::back(args),
+        Command::Forward => command::forward(args),
+        Command::Trust(args_trust) => command::trust(args_trust, &config),
+        Command::ImportZoxide(args_import_zoxide) => command::import_zoxide(args_import_zoxide),
     }
 }
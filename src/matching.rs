@@ -0,0 +1,318 @@
+use std::cmp::Reverse;
+
+use anyhow::Context as _;
+use regex::RegexBuilder;
+
+use crate::config::{CaseSensitivity, MatchingMode, MatchingScope, SessionSelectorMatching};
+
+/// The score per character of the pattern of matches, which are not fuzzy
+/// matches, so they are ranked similar to exact fuzzy matches.
+const SCORE_PER_CHAR: u16 = 16;
+
+/// Returns an error, if the pattern is invalid in the given mode, as matching
+/// itself treats invalid patterns as matching nothing.
+pub fn validate(pattern: &str, mode: MatchingMode) -> anyhow::Result<()> {
+    if mode == MatchingMode::Regex {
+        RegexBuilder::new(pattern)
+            .build()
+            .with_context(|| format!("invalid regex pattern '{pattern}'"))?;
+    }
+    Ok(())
+}
+
+/// Matches the pattern against all haystacks in the given mode and returns the
+/// matches sorted by their score. The matched indices always refer to the
+/// characters of the full haystacks. An empty pattern matches all haystacks in
+/// their order.
+pub fn matches(
+    pattern: &str,
+    haystacks: &[String],
+    matching: &SessionSelectorMatching,
+    mode: MatchingMode,
+) -> Vec<frizbee::Match> {
+    if pattern.is_empty() {
+        return (0..haystacks.len())
+            .map(|index| frizbee::Match {
+                index_in_haystack: index,
+                indices: Some(Vec::new()),
+                score: 0,
+                exact: false,
+            })
+            .collect();
+    }
+    let is_case_sensitive = match matching.case_sensitivity {
+        CaseSensitivity::Smart => pattern.chars().any(char::is_uppercase),
+        CaseSensitivity::Sensitive => true,
+        CaseSensitivity::Insensitive => false,
+    };
+    // The offsets of the matched parts in characters.
+    let (haystacks, offsets): (Vec<&str>, Vec<usize>) = match matching.scope {
+        MatchingScope::Path => (
+            haystacks.iter().map(String::as_str).collect(),
+            vec![0; haystacks.len()],
+        ),
+        MatchingScope::Basename => haystacks
+            .iter()
+            .map(|haystack| {
+                let basename = haystack
+                    .trim_end_matches('/')
+                    .rsplit('/')
+                    .next()
+                    .unwrap_or_default();
+                let offset =
+                    haystack.trim_end_matches('/').chars().count() - basename.chars().count();
+                (basename, offset)
+            })
+            .unzip(),
+    };
+
+    let mut matches = match mode {
+        MatchingMode::Fuzzy | MatchingMode::FuzzyNoTypos => {
+            fuzzy_matches(pattern, &haystacks, matching, mode, is_case_sensitive)
+        }
+        MatchingMode::Exact | MatchingMode::Prefix => {
+            substring_matches(pattern, &haystacks, mode, is_case_sensitive)
+        }
+        MatchingMode::Regex => regex_matches(pattern, &haystacks, is_case_sensitive),
+    };
+
+    for m in &mut matches {
+        for i in m.indices.iter_mut().flatten() {
+            *i += offsets[m.index_in_haystack];
+        }
+    }
+    matches.sort_by_key(|m| Reverse(m.score));
+    matches
+}
+
+fn fuzzy_matches(
+    pattern: &str,
+    haystacks: &[&str],
+    matching: &SessionSelectorMatching,
+    mode: MatchingMode,
+    is_case_sensitive: bool,
+) -> Vec<frizbee::Match> {
+    let pattern_len = pattern.chars().count();
+    #[allow(
+        clippy::cast_possible_truncation,
+        clippy::cast_precision_loss,
+        clippy::cast_sign_loss
+    )]
+    let max_typos = match mode {
+        MatchingMode::Fuzzy => (pattern_len as f64 * matching.max_typos_per_char) as u16,
+        _ => 0,
+    };
+    frizbee::match_list(
+        pattern,
+        haystacks,
+        #[allow(clippy::cast_possible_truncation)]
+        frizbee::Options {
+            min_score: (pattern_len as u16).saturating_mul(matching.min_score_per_char),
+            max_typos: Some(max_typos),
+            matched_indices: true,
+            ..Default::default()
+        },
+    )
+    .into_iter()
+    // Fuzzy matching always ignores the case.
+    .filter(|m| !is_case_sensitive || is_case_matched(pattern, haystacks[m.index_in_haystack]))
+    .collect()
+}
+
+fn substring_matches(
+    pattern: &str,
+    haystacks: &[&str],
+    mode: MatchingMode,
+    is_case_sensitive: bool,
+) -> Vec<frizbee::Match> {
+    let pattern = pattern.chars().collect::<Vec<_>>();
+    haystacks
+        .iter()
+        .enumerate()
+        .filter_map(|(index, haystack)| {
+            let haystack = haystack.chars().collect::<Vec<_>>();
+            let pos = if mode == MatchingMode::Prefix {
+                (haystack.len() >= pattern.len()
+                    && chars_eq(&haystack[..pattern.len()], &pattern, is_case_sensitive))
+                .then_some(0)?
+            } else {
+                haystack
+                    .windows(pattern.len())
+                    .position(|window| chars_eq(window, &pattern, is_case_sensitive))?
+            };
+            Some(new_match(index, (pos..pos + pattern.len()).collect()))
+        })
+        .collect()
+}
+
+fn regex_matches(
+    pattern: &str,
+    haystacks: &[&str],
+    is_case_sensitive: bool,
+) -> Vec<frizbee::Match> {
+    let Ok(regex) = RegexBuilder::new(pattern)
+        .case_insensitive(!is_case_sensitive)
+        .build()
+    else {
+        // The pattern is usually invalid, while it is being typed.
+        return Vec::new();
+    };
+    haystacks
+        .iter()
+        .enumerate()
+        .filter_map(|(index, haystack)| {
+            let m = regex.find(haystack)?;
+            let start = haystack[..m.start()].chars().count();
+            let len = m.as_str().chars().count();
+            Some(new_match(index, (start..start + len).collect()))
+        })
+        .collect()
+}
+
+fn new_match(index: usize, indices: Vec<usize>) -> frizbee::Match {
+    #[allow(clippy::cast_possible_truncation)]
+    let score = (indices.len() as u16).saturating_mul(SCORE_PER_CHAR);
+    frizbee::Match {
+        index_in_haystack: index,
+        indices: Some(indices),
+        score,
+        exact: true,
+    }
+}
+
+fn chars_eq(chars_a: &[char], chars_b: &[char], is_case_sensitive: bool) -> bool {
+    chars_a
+        .iter()
+        .zip(chars_b)
+        .all(|(c_a, c_b)| char_eq(*c_a, *c_b, is_case_sensitive))
+}
+
+fn char_eq(c_a: char, c_b: char, is_case_sensitive: bool) -> bool {
+    if is_case_sensitive {
+        c_a == c_b
+    } else {
+        c_a.to_lowercase().eq(c_b.to_lowercase())
+    }
+}
+
+/// Returns whether the fuzzy matched haystack contains the characters of the
+/// pattern in order in the exact case, if it contains them in order regardless
+/// of the case. Matches with typos are always kept, as their case cannot be
+/// compared.
+fn is_case_matched(pattern: &str, haystack: &str) -> bool {
+    is_subsequence(pattern, haystack, true) || !is_subsequence(pattern, haystack, false)
+}
+
+fn is_subsequence(pattern: &str, haystack: &str, is_case_sensitive: bool) -> bool {
+    let mut haystack_chars = haystack.chars();
+    pattern
+        .chars()
+        .all(|c| haystack_chars.any(|haystack_c| char_eq(haystack_c, c, is_case_sensitive)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn haystacks() -> Vec<String> {
+        vec!["/home/user/foo".to_owned(), "/home/user/bar".to_owned()]
+    }
+
+    #[test]
+    fn empty_pattern_matches_all_haystacks() {
+        for mode in [
+            MatchingMode::Fuzzy,
+            MatchingMode::FuzzyNoTypos,
+            MatchingMode::Exact,
+            MatchingMode::Prefix,
+            MatchingMode::Regex,
+        ] {
+            let matches = matches("", &haystacks(), &SessionSelectorMatching::default(), mode);
+            assert_eq!(
+                matches
+                    .iter()
+                    .map(|m| (m.index_in_haystack, m.indices.clone()))
+                    .collect::<Vec<_>>(),
+                vec![(0, Some(Vec::new())), (1, Some(Vec::new()))],
+                "{mode:?}"
+            );
+        }
+    }
+
+    fn matched_indices(
+        pattern: &str,
+        haystacks: &[&str],
+        matching: &SessionSelectorMatching,
+        mode: MatchingMode,
+    ) -> Vec<(usize, Vec<usize>)> {
+        let haystacks = haystacks
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>();
+        let mut matches = matches(pattern, &haystacks, matching, mode)
+            .into_iter()
+            .map(|m| (m.index_in_haystack, m.indices.unwrap_or_default()))
+            .collect::<Vec<_>>();
+        matches.sort();
+        matches
+    }
+
+    #[test]
+    fn exact_and_prefix_modes_match_substrings_and_prefixes() {
+        let matching = SessionSelectorMatching::default();
+        let haystacks = ["/home/foo", "/home/bar", "/foo/x"];
+        assert_eq!(
+            matched_indices("foo", &haystacks, &matching, MatchingMode::Exact),
+            vec![(0, vec![6, 7, 8]), (2, vec![1, 2, 3])]
+        );
+        assert_eq!(
+            matched_indices("/home", &haystacks, &matching, MatchingMode::Prefix),
+            vec![(0, vec![0, 1, 2, 3, 4]), (1, vec![0, 1, 2, 3, 4])]
+        );
+        assert!(matched_indices("foo", &haystacks, &matching, MatchingMode::Prefix).is_empty());
+    }
+
+    #[test]
+    fn smart_case_is_only_sensitive_with_uppercase_characters() {
+        let matching = SessionSelectorMatching::default();
+        let haystacks = ["/x/Foo", "/x/foo"];
+        for mode in [
+            MatchingMode::Fuzzy,
+            MatchingMode::Exact,
+            MatchingMode::Regex,
+        ] {
+            let indices = |pattern| {
+                matched_indices(pattern, &haystacks, &matching, mode)
+                    .into_iter()
+                    .map(|(i, _)| i)
+                    .collect::<Vec<_>>()
+            };
+            assert_eq!(indices("foo"), vec![0, 1], "{mode:?}");
+            assert_eq!(indices("Foo"), vec![0], "{mode:?}");
+        }
+    }
+
+    #[test]
+    fn basename_scope_offsets_the_indices() {
+        let matching = SessionSelectorMatching {
+            scope: MatchingScope::Basename,
+            ..Default::default()
+        };
+        let haystacks = ["/foo/bar/", "/bar/foo/"];
+        assert_eq!(
+            matched_indices("foo", &haystacks, &matching, MatchingMode::Exact),
+            vec![(1, vec![5, 6, 7])]
+        );
+        assert_eq!(
+            matched_indices("^f", &haystacks, &matching, MatchingMode::Regex),
+            vec![(1, vec![5])]
+        );
+    }
+
+    #[test]
+    fn validate_rejects_invalid_regex() {
+        assert!(validate("(", MatchingMode::Regex).is_err());
+        assert!(validate("(", MatchingMode::Exact).is_ok());
+        assert!(validate("^/home", MatchingMode::Regex).is_ok());
+    }
+}
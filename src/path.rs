@@ -0,0 +1,33 @@
+use std::path::{Path, PathBuf};
+
+use crate::config::SessionSelectorPaths;
+
+/// Expands a leading `~` of the path to the home directory.
+pub fn expand_home_dir(path: &str) -> PathBuf {
+    match path.strip_prefix('~') {
+        Some(path_stripped) if path_stripped.is_empty() || path_stripped.starts_with('/') => {
+            dirs::home_dir()
+                .unwrap_or_default()
+                .join(path_stripped.trim_start_matches('/'))
+        }
+        _ => PathBuf::from(path),
+    }
+}
+
+/// Formats the path for display as configured, which is also the haystack,
+/// that patterns are matched against.
+pub fn display(path: &Path, paths: &SessionSelectorPaths) -> String {
+    let mut path_display = String::new();
+    match path.strip_prefix(dirs::home_dir().unwrap_or_default()) {
+        Ok(path_stripped) if paths.truncate_home_dir => {
+            path_display.push_str(&paths.home_dir_symbol);
+            path_display.push('/');
+            path_display.push_str(&path_stripped.to_string_lossy());
+        }
+        _ => path_display.push_str(&path.to_string_lossy()),
+    }
+    if paths.trailing_slash {
+        path_display.push('/');
+    }
+    path_display
+}
@@ -0,0 +1,50 @@
+use std::{path::PathBuf, sync::LazyLock};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{APP_NAME, config::SessionSelectorQueryHistory, json_store};
+
+static QUERY_HISTORY_FILE_PATH: LazyLock<PathBuf> = LazyLock::new(|| {
+    dirs::cache_dir()
+        .unwrap_or_default()
+        .join(APP_NAME)
+        .join("query_history.json")
+});
+
+/// A bounded history of the queries of the session selector, which led to a
+/// confirmed switch, ordered from least to most recently used.
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(transparent)]
+pub struct QueryHistory {
+    queries: Vec<String>,
+}
+
+impl QueryHistory {
+    pub fn read() -> anyhow::Result<Self> {
+        json_store::read(&QUERY_HISTORY_FILE_PATH)
+    }
+
+    pub fn write(&self) -> anyhow::Result<()> {
+        json_store::write(&QUERY_HISTORY_FILE_PATH, self)
+    }
+
+    pub fn queries(&self) -> &[String] {
+        &self.queries
+    }
+
+    fn push(&mut self, query: &str, query_history: &SessionSelectorQueryHistory) {
+        if query_history.deduplicate {
+            self.queries.retain(|q| q != query);
+        }
+        self.queries.push(query.to_string());
+        let excess_len = self.queries.len().saturating_sub(query_history.size);
+        self.queries.drain(..excess_len);
+    }
+}
+
+/// Records the query as the most recently used one.
+pub fn record(query: &str, query_history: &SessionSelectorQueryHistory) -> anyhow::Result<()> {
+    let mut history = QueryHistory::read()?;
+    history.push(query, query_history);
+    history.write()
+}
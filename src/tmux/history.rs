@@ -0,0 +1,97 @@
+use std::{path::PathBuf, sync::LazyLock};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{APP_NAME, json_store};
+
+use super::Session;
+
+const SESSIONS_LEN_MAX: usize = 64;
+static HISTORY_FILE_PATH: LazyLock<PathBuf> = LazyLock::new(|| {
+    dirs::cache_dir()
+        .unwrap_or_default()
+        .join(APP_NAME)
+        .join("history.json")
+});
+
+/// A bounded history of the sessions switched to, ordered from least to most
+/// recently used, with a position, which can be moved back and forward.
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct History {
+    sessions: Vec<Session>,
+    pos: usize,
+}
+
+impl History {
+    /// Reads the history, removing all sessions, which no longer exist.
+    pub fn read() -> anyhow::Result<Self> {
+        let history = json_store::read::<Self>(&HISTORY_FILE_PATH)?;
+
+        let sessions_all = Session::all()?;
+        let mut sessions = Vec::with_capacity(history.sessions.len());
+        let mut sessions_up_to_pos_len = 0_usize;
+        for (i, session) in history.sessions.iter().enumerate() {
+            if let Some(session) = sessions_all.iter().find(|s| *s == session) {
+                sessions.push(session.clone());
+                if i <= history.pos {
+                    sessions_up_to_pos_len += 1;
+                }
+            }
+        }
+        Ok(Self {
+            sessions,
+            pos: sessions_up_to_pos_len.saturating_sub(1),
+        })
+    }
+
+    /// Records a switch from the first to the second session.
+    pub fn record_switch(from_opt: Option<&Session>, to: &Session) -> anyhow::Result<()> {
+        let mut history = Self::read()?;
+        if let Some(from) = from_opt
+            && history.current() != Some(from)
+        {
+            history.push(from);
+        }
+        history.push(to);
+        history.write()
+    }
+
+    pub fn current(&self) -> Option<&Session> {
+        self.sessions.get(self.pos)
+    }
+
+    pub fn previous(&self) -> Option<&Session> {
+        self.pos
+            .checked_sub(1)
+            .and_then(|pos| self.sessions.get(pos))
+    }
+
+    /// Discards all sessions after the current position and moves the given
+    /// session to the end of the history.
+    pub fn push(&mut self, session: &Session) {
+        self.sessions.truncate(self.pos + 1);
+        self.sessions.retain(|s| s != session);
+        self.sessions.push(session.clone());
+        if self.sessions.len() > SESSIONS_LEN_MAX {
+            self.sessions
+                .drain(..self.sessions.len() - SESSIONS_LEN_MAX);
+        }
+        self.pos = self.sessions.len() - 1;
+    }
+
+    pub fn go_back(&mut self) -> Option<Session> {
+        let session = self.previous()?.clone();
+        self.pos -= 1;
+        Some(session)
+    }
+
+    pub fn go_forward(&mut self) -> Option<Session> {
+        let session = self.sessions.get(self.pos + 1)?.clone();
+        self.pos += 1;
+        Some(session)
+    }
+
+    pub fn write(&self) -> anyhow::Result<()> {
+        json_store::write(&HISTORY_FILE_PATH, self)
+    }
+}
@@ -1,32 +1,150 @@
+mod history;
 mod session;
 
-use tmux_interface::{BindKey, DisplayPopup, RunShell, Tmux, TmuxCommands};
+use std::sync::OnceLock;
 
-use crate::config::Config;
+use tmux_interface::{
+    BindKey, DisplayMessage, DisplayPopup, KillPane, NewWindow, PaneSize, RunShell, SetOption,
+    ShowOptions, Size, SplitWindow, StartServer, Tmux, TmuxCommands, Version,
+};
 
-pub use self::session::Session;
+use crate::{
+    APP_NAME,
+    config::{Config, SelectorHost},
+};
+
+pub use self::{history::History, session::Session};
 
 const ENV_VAR_KEY: &str = "TMUX";
+const PANE_ENV_VAR_KEY: &str = "TMUX_PANE";
+const EXIT_EMPTY_OPTION: &str = "exit-empty";
+
+/// Whether the tmux version supports popups, which is only detected once.
+static SUPPORTS_POPUPS: OnceLock<bool> = OnceLock::new();
+
+pub fn is_in_session() -> bool {
+    std::env::var_os(ENV_VAR_KEY).is_some()
+}
 
 pub fn assert_in_session() -> anyhow::Result<()> {
-    std::env::var(ENV_VAR_KEY)
-        .map(|_| ())
-        .map_err(|_| anyhow::format_err!("you are not in a tmux session"))
+    if !is_in_session() {
+        anyhow::bail!("you are not in a tmux session");
+    }
+    Ok(())
+}
+
+/// Starts the tmux server, unless it is already running, which loads the tmux
+/// configuration and thereby may restore sessions. The server keeps running
+/// without sessions until the returned previous value of the `exit-empty`
+/// option is restored.
+pub fn start_server() -> anyhow::Result<String> {
+    let mut tmux_cmds = TmuxCommands::new();
+    tmux_cmds.push(StartServer::new());
+    tmux_cmds.push(
+        ShowOptions::new()
+            .server()
+            .value()
+            .option(EXIT_EMPTY_OPTION),
+    );
+    tmux_cmds.push(
+        SetOption::new()
+            .server()
+            .option(EXIT_EMPTY_OPTION)
+            .value("off"),
+    );
+    let output = Tmux::with_commands(tmux_cmds).output()?;
+    if !output.success() {
+        anyhow::bail!(
+            "failed to start tmux server: {}",
+            String::from_utf8_lossy(&output.stderr()).trim()
+        );
+    }
+    Ok(output.to_string().trim().to_string())
+}
+
+/// Restores the `exit-empty` option, after the tmux server has been started,
+/// so it exits again once there are no sessions.
+pub fn restore_exit_empty(exit_empty: &str) -> anyhow::Result<()> {
+    Tmux::with_command(
+        SetOption::new()
+            .server()
+            .option(EXIT_EMPTY_OPTION)
+            .value(exit_empty),
+    )
+    .status()?;
+    Ok(())
+}
+
+/// Kills the tmux pane the current process runs in.
+pub fn kill_current_pane() -> anyhow::Result<()> {
+    let pane_id = std::env::var(PANE_ENV_VAR_KEY)?;
+    Tmux::with_command(KillPane::new().target_pane(pane_id)).status()?;
+    Ok(())
+}
+
+/// Returns whether the tmux pane the current process runs in is the only pane
+/// of its window.
+pub fn is_current_pane_alone() -> anyhow::Result<bool> {
+    let pane_id = std::env::var(PANE_ENV_VAR_KEY)?;
+    let output = Tmux::with_command(
+        DisplayMessage::new()
+            .print()
+            .target_pane(pane_id)
+            .message("#{window_panes}"),
+    )
+    .output()?;
+    Ok(output.to_string().trim() == "1")
 }
 
 pub fn set_up(config: &Config) -> anyhow::Result<()> {
     let mut tmux_cmds = TmuxCommands::new();
 
-    let cmd_select_session = DisplayPopup::new()
-        .width(config.session_selector.width.clone())
-        .height(config.session_selector.height.clone())
-        .no_border()
-        .shell_command(format!(
-            r#""{} select""#,
-            std::env::current_exe()?.to_string_lossy()
-        ))
-        .close_on_exit()
-        .build();
+    let host = match config.session_selector.host {
+        SelectorHost::Popup if !supports_popups()? => SelectorHost::Window,
+        host => host,
+    };
+    let pane_size = match config.session_selector.height {
+        Size::Size(size) => PaneSize::Size(size),
+        Size::Percentage(percentage) => PaneSize::Percentage(percentage),
+    };
+    let current_exe = std::env::current_exe()?;
+    let current_exe = current_exe.to_string_lossy();
+    // Formats are only expanded in the commands run by `run-shell`, so the
+    // temporary pane is created from there to pass the client, which pressed
+    // the key, to the session selector.
+    let cmd_select_temporary_pane =
+        format!("'{current_exe} select --temporary-pane --target-client #{{q:client_name}}'");
+    let cmd_select_session = match host {
+        SelectorHost::Popup => DisplayPopup::new()
+            .width(config.session_selector.width.clone())
+            .height(config.session_selector.height.clone())
+            .no_border()
+            .shell_command(format!(r#""{current_exe} select""#))
+            .close_on_exit()
+            .build(),
+        SelectorHost::Window => {
+            let new_window = NewWindow::new()
+                .target_window("#{q:session_id}:")
+                .window_name(APP_NAME)
+                .shell_command(cmd_select_temporary_pane)
+                .build();
+            RunShell::new()
+                .shell_command(format!(r#""tmux {new_window}""#))
+                .build()
+        }
+        SelectorHost::Split => {
+            let split_window = SplitWindow::new()
+                .target_pane("#{q:pane_id}")
+                .full()
+                .vertical()
+                .size(&pane_size)
+                .shell_command(cmd_select_temporary_pane)
+                .build();
+            RunShell::new()
+                .shell_command(format!(r#""tmux {split_window}""#))
+                .build()
+        }
+    };
     for key in &config.keybinds.select_session {
         tmux_cmds.push(
             BindKey::new()
@@ -35,21 +153,39 @@ pub fn set_up(config: &Config) -> anyhow::Result<()> {
         );
     }
 
-    let cmd_last_session = RunShell::new()
-        .shell_command(format!(
-            r#""{} last""#,
-            std::env::current_exe()?.to_string_lossy()
-        ))
-        .build();
-    for key in &config.keybinds.last_session {
-        tmux_cmds.push(
-            BindKey::new()
-                .key(key)
-                .command(cmd_last_session.to_string()),
-        );
+    let run_shell_bindings = [
+        ("last", &config.keybinds.last_session),
+        ("back", &config.keybinds.history_back),
+        ("forward", &config.keybinds.history_forward),
+    ];
+    for (subcommand, keys) in run_shell_bindings {
+        let cmd = RunShell::new()
+            .shell_command(format!(r#""{current_exe} {subcommand}""#))
+            .build();
+        for key in keys {
+            tmux_cmds.push(BindKey::new().key(key).command(cmd.to_string()));
+        }
     }
 
     Tmux::with_commands(tmux_cmds).status()?;
 
     Ok(())
 }
+
+/// Returns whether the tmux version supports popups, which have been added in
+/// tmux 3.2.
+fn supports_popups() -> anyhow::Result<bool> {
+    if let Some(supports_popups) = SUPPORTS_POPUPS.get() {
+        return Ok(*supports_popups);
+    }
+    let output = Tmux::new().version().output()?;
+    // Development builds like `tmux master` are assumed to be recent. Their
+    // version is parsed as 0.0 instead of failing.
+    let supports_popups = output
+        .to_string()
+        .parse::<Version>()
+        .map_or(true, |version| {
+            version.major == 0 || (version.major, version.minor) >= (3, 2)
+        });
+    Ok(*SUPPORTS_POPUPS.get_or_init(|| supports_popups))
+}
@@ -5,21 +5,41 @@ use std::{
 
 use serde::{Deserialize, Serialize};
 use tmux_interface::{
-    AttachSession, DisplayMessage, HasSession, ListSessions, NewSession, RenameSession,
-    SwitchClient, Tmux,
+    AttachSession, CapturePane, DetachClient, DisplayMessage, KillSession, KillWindow, ListClients,
+    ListSessions, ListWindows, MoveWindow, NewSession, NewWindow, SelectLayout, SelectPane,
+    SelectWindow, SendKeys, SetOption, SplitWindow, SwitchClient, Tmux, TmuxCommands,
 };
 
-use crate::{APP_NAME, tmux};
+use crate::{
+    APP_NAME,
+    config::{Config, Layout, LayoutSplit},
+    tmux::{self, History},
+};
 
-const FORMAT: &str =
-    r##"{"id":"#{session_id}","name":"#{session_name}","path":"#{session_path}"}"##;
-static NAME_PREFIX: LazyLock<String> = LazyLock::new(|| format!("{APP_NAME}_"));
-static LAST_SESSION_FILE_PATH: LazyLock<PathBuf> = LazyLock::new(|| {
-    dirs::cache_dir()
-        .unwrap_or_default()
-        .join(APP_NAME)
-        .join("last_session.json")
+const WINDOW_FORMAT: &str = "#{window_id} #{pane_id}";
+const PANE_FORMAT: &str = "#{pane_id}";
+/// The session-scoped user option, which marks sessions as managed by
+/// tmuxion and holds the path they have been created for.
+static PATH_OPTION: LazyLock<String> = LazyLock::new(|| format!("@{APP_NAME}_path"));
+/// The session format, whose fields are separated by spaces, which tmux
+/// escapes within the text fields like all characters special to the shell.
+static FORMAT: LazyLock<String> = LazyLock::new(|| {
+    [
+        "#{q:session_id}",
+        "#{q:session_name}",
+        &format!("#{{q:{}}}", PATH_OPTION.as_str()),
+        "#{q:session_path}",
+        "#{session_windows}",
+        "#{session_attached}",
+        "#{session_activity}",
+        "#{session_created}",
+        "#{?session_last_attached,#{session_last_attached},0}",
+    ]
+    .join(" ")
 });
+/// The prefix of the names of sessions, which have been created by older
+/// versions of tmuxion, before sessions were marked with the path option.
+static LEGACY_NAME_PREFIX: LazyLock<String> = LazyLock::new(|| format!("{APP_NAME}_"));
 
 #[derive(Debug, Clone, Eq, Deserialize, Serialize)]
 pub struct Session {
@@ -28,10 +48,22 @@ pub struct Session {
     name: String,
     #[serde(default, skip_serializing)]
     path: PathBuf,
+    #[serde(default, skip_serializing)]
+    tmux_path: PathBuf,
+    #[serde(default, skip_serializing)]
+    windows: usize,
+    #[serde(default, skip_serializing)]
+    attached: usize,
+    #[serde(default, skip_serializing)]
+    activity: u64,
+    #[serde(default, skip_serializing)]
+    created: u64,
+    #[serde(default, skip_serializing)]
+    last_attached: u64,
 }
 
 impl Session {
-    pub fn new(path: &Path) -> anyhow::Result<(Self, bool)> {
+    pub fn new(path: &Path, config: &Config) -> anyhow::Result<(Self, bool)> {
         let sessions = Self::all()?;
         let sessions_find_fn = |session: &Session| {
             let Ok(session_path) = session.path.canonicalize() else {
@@ -43,26 +75,32 @@ impl Session {
         if let Some(session) = session_opt {
             return Ok((session, true));
         }
-        let output = Tmux::with_command(
-            NewSession::new()
-                .start_directory(path.to_string_lossy())
-                .detached()
-                .format(FORMAT)
-                .print(),
-        )
-        .output()?;
-        let session = serde_json::from_str::<Self>(&output.to_string())?;
-        Tmux::with_command(
-            RenameSession::new()
-                .target_session(&session.id)
-                .new_name(format!("{}{}", NAME_PREFIX.as_str(), session.id)),
-        )
-        .status()?;
+        // The project configuration is only loaded for a new session, as it
+        // may execute the project file and ask to trust it.
+        let project = config.project(path)?;
+        // The name is resolved before creating the session, so a failing
+        // naming function does not leave an unmanaged session behind.
+        let name_opt = get_available_name(&project.session_name(path)?)?;
+        let mut new_session = NewSession::new()
+            .start_directory(path.to_string_lossy())
+            .detached()
+            .format(FORMAT.as_str())
+            .print();
+        if let Some(name) = &name_opt {
+            new_session = new_session.session_name(name);
+        }
+        for (variable, value) in &project.environment {
+            new_session = new_session.environment(variable, value);
+        }
+        let output = Tmux::with_command(new_session).output()?;
+        let mut session = Self::parse(&output.to_string())?;
+        session.mark_as_managed(path)?;
+        session.apply_layout(&project.layout)?;
         Ok((session, false))
     }
 
     pub fn current(target_client_opt: Option<&String>) -> anyhow::Result<Option<Self>> {
-        let mut display_message = DisplayMessage::new().message(FORMAT).print();
+        let mut display_message = DisplayMessage::new().message(FORMAT.as_str()).print();
         if let Some(target_client) = target_client_opt {
             // For the `display-message` command the `target-client` option only
             // controls in which client's status line the message is displayed
@@ -70,57 +108,117 @@ impl Session {
             display_message = display_message.target_pane(target_client);
         }
         let output = Tmux::with_command(display_message).output()?;
-        let session = serde_json::from_str::<Self>(&output.to_string())?;
-        Ok(session
-            .name
-            .starts_with(NAME_PREFIX.as_str())
-            .then_some(session))
+        let mut session = Self::parse(&output.to_string())?;
+        Ok(session.is_managed()?.then_some(session))
     }
 
+    /// Returns the previous session in the history.
     pub fn last() -> anyhow::Result<Option<Self>> {
-        let content = match std::fs::read_to_string(LAST_SESSION_FILE_PATH.as_path()) {
-            Ok(content) => content,
-            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
-            Err(err) => anyhow::bail!(err),
-        };
-        let session = serde_json::from_str::<Self>(&content)?;
-        let exists = Tmux::with_command(HasSession::new().target_session(&session.id))
-            .status()?
-            .success();
-        Ok(exists.then_some(session))
+        Ok(History::read()?.previous().cloned())
     }
 
     pub fn all() -> anyhow::Result<Vec<Self>> {
-        let output = Tmux::with_command(ListSessions::new().format(FORMAT)).output()?;
-        let mut sessions = output
-            .to_string()
-            .lines()
-            .map(serde_json::from_str)
-            .collect::<Result<Vec<Self>, _>>()?
-            .into_iter()
-            .filter(|session| session.name.starts_with(NAME_PREFIX.as_str()))
-            .collect::<Vec<_>>();
+        let output = Tmux::with_command(ListSessions::new().format(FORMAT.as_str())).output()?;
+        let mut sessions = Vec::new();
+        for line in output.to_string().lines() {
+            let mut session = Self::parse(line)?;
+            if session.is_managed()? {
+                sessions.push(session);
+            }
+        }
         sessions.sort();
         Ok(sessions)
     }
 
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
     pub fn path(&self) -> &PathBuf {
         &self.path
     }
 
-    pub fn save_as_last(&self) -> anyhow::Result<()> {
-        if let Some(last_session_file_path_parent) = LAST_SESSION_FILE_PATH.parent() {
-            std::fs::create_dir_all(last_session_file_path_parent)?;
+    pub fn windows(&self) -> usize {
+        self.windows
+    }
+
+    /// Excludes a window, which is about to be closed, from the number of
+    /// windows.
+    pub fn exclude_window(&mut self) {
+        self.windows = self.windows.saturating_sub(1);
+    }
+
+    pub fn attached(&self) -> usize {
+        self.attached
+    }
+
+    pub fn activity(&self) -> u64 {
+        self.activity
+    }
+
+    pub fn created(&self) -> u64 {
+        self.created
+    }
+
+    /// Returns the time the session has been attached last, which is `0` if
+    /// it has never been attached.
+    pub fn last_attached(&self) -> u64 {
+        self.last_attached
+    }
+
+    pub fn kill(&self) -> anyhow::Result<()> {
+        Tmux::with_command(KillSession::new().target_session(&self.id)).status()?;
+        Ok(())
+    }
+
+    /// Sends the command to the session's active pane.
+    pub fn send_command(&self, command: &str) -> anyhow::Result<()> {
+        send_command(&self.id, Some(&command.to_string()))
+    }
+
+    /// Returns the contents of the session's active pane including the escape
+    /// sequences for text and background attributes.
+    pub fn capture_active_pane(&self) -> anyhow::Result<String> {
+        let output = Tmux::with_command(
+            CapturePane::new()
+                .stdout()
+                .escape_sequences()
+                .target_pane(&self.id),
+        )
+        .output()?;
+        Ok(output.to_string())
+    }
+
+    /// Detaches all clients attached to the session, except the current one.
+    pub fn detach_other_clients(&self, target_client_opt: Option<&String>) -> anyhow::Result<()> {
+        let mut display_message = DisplayMessage::new().message("#{client_name}").print();
+        if let Some(target_client) = target_client_opt {
+            // Unlike the `target-pane` option, which resolves to the most
+            // recently used client of the session, the `target-client` option
+            // resolves to the given client.
+            display_message = display_message.target_client(target_client);
+        }
+        let current_client = Tmux::with_command(display_message).output()?.to_string();
+        let output = Tmux::with_command(
+            ListClients::new()
+                .target_session(&self.id)
+                .format("#{client_name}"),
+        )
+        .output()?;
+        for client in output.to_string().lines() {
+            if client != current_client.trim() {
+                Tmux::with_command(DetachClient::new().target_client(client)).status()?;
+            }
         }
-        std::fs::write(
-            LAST_SESSION_FILE_PATH.as_path(),
-            serde_json::to_string(&self)?,
-        )?;
         Ok(())
     }
 
     pub fn switch_to(&self, target_client_opt: Option<&String>) -> anyhow::Result<()> {
-        if tmux::assert_in_session().is_ok() {
+        if tmux::is_in_session() {
             let mut switch_client = SwitchClient::new().target_session(&self.id);
             if let Some(target_client) = target_client_opt {
                 switch_client = switch_client.target_client(target_client);
@@ -131,6 +229,235 @@ impl Session {
         }
         Ok(())
     }
+
+    /// Parses a session from the output of the session format.
+    fn parse(output: &str) -> anyhow::Result<Self> {
+        let output = output.strip_suffix('\n').unwrap_or(output);
+        let mut fields = Vec::new();
+        let mut field = String::new();
+        let mut chars = output.chars();
+        while let Some(c) = chars.next() {
+            match c {
+                '\\' => field.extend(chars.next()),
+                ' ' => fields.push(std::mem::take(&mut field)),
+                _ => field.push(c),
+            }
+        }
+        fields.push(field);
+        let [
+            id,
+            name,
+            path,
+            tmux_path,
+            windows,
+            attached,
+            activity,
+            created,
+            last_attached,
+        ] = &fields[..]
+        else {
+            anyhow::bail!("failed to parse session '{output}'");
+        };
+        Ok(Self {
+            id: id.clone(),
+            name: name.clone(),
+            path: PathBuf::from(path),
+            tmux_path: PathBuf::from(tmux_path),
+            windows: windows.parse()?,
+            attached: attached.parse()?,
+            activity: activity.parse()?,
+            created: created.parse()?,
+            last_attached: last_attached.parse()?,
+        })
+    }
+
+    /// Checks whether the session is managed by tmuxion and marks sessions,
+    /// which have been created by older versions of tmuxion, as managed.
+    fn is_managed(&mut self) -> anyhow::Result<bool> {
+        if !self.path.as_os_str().is_empty() {
+            return Ok(true);
+        }
+        if !self.name.starts_with(LEGACY_NAME_PREFIX.as_str()) {
+            return Ok(false);
+        }
+        let path = self
+            .tmux_path
+            .canonicalize()
+            .unwrap_or_else(|_| self.tmux_path.clone());
+        self.mark_as_managed(&path)?;
+        Ok(true)
+    }
+
+    fn mark_as_managed(&mut self, path: &Path) -> anyhow::Result<()> {
+        Tmux::with_command(
+            SetOption::new()
+                .target_pane(&self.id)
+                .option(PATH_OPTION.as_str())
+                .value(path.to_string_lossy()),
+        )
+        .status()?;
+        self.path = path.to_path_buf();
+        Ok(())
+    }
+
+    fn apply_layout(&self, layout: &Layout) -> anyhow::Result<()> {
+        if layout.windows.is_empty() {
+            return Ok(());
+        }
+
+        let output = Tmux::with_command(
+            ListWindows::new()
+                .target_session(&self.id)
+                .format("#{window_id}"),
+        )
+        .output()?;
+        let initial_window_ids = output
+            .to_string()
+            .lines()
+            .map(String::from)
+            .collect::<Vec<_>>();
+
+        let mut window_id_to_focus_opt = Option::<String>::None;
+        let mut pane_ids_to_focus = Vec::new();
+        for window in &layout.windows {
+            let window_path = window
+                .path
+                .as_ref()
+                .map_or_else(|| self.path.clone(), |path| self.path.join(path));
+            let pane_path_fn = |pane_path_opt: Option<&PathBuf>| {
+                pane_path_opt.map_or_else(|| window_path.clone(), |path| window_path.join(path))
+            };
+            let mut panes = window.panes.iter();
+
+            let first_pane_opt = panes.next();
+            let first_pane_path = pane_path_fn(first_pane_opt.and_then(|pane| pane.path.as_ref()));
+            let mut new_window = NewWindow::new()
+                .detached()
+                .target_window(format!("{}:", self.id))
+                .start_directory(first_pane_path.to_string_lossy())
+                .print()
+                .format(WINDOW_FORMAT);
+            if let Some(name) = &window.name {
+                new_window = new_window.window_name(name);
+            }
+            let output = Tmux::with_command(new_window).output()?.to_string();
+            let (window_id, mut pane_id) = output
+                .trim()
+                .split_once(' ')
+                .map(|(window_id, pane_id)| (window_id.to_string(), pane_id.to_string()))
+                .ok_or(anyhow::format_err!("failed to create window '{output}'"))?;
+            if let Some(first_pane) = first_pane_opt {
+                send_command(&pane_id, first_pane.command.as_ref())?;
+                if first_pane.focus {
+                    pane_ids_to_focus.push(pane_id.clone());
+                }
+            }
+
+            for pane in panes {
+                let pane_path = pane_path_fn(pane.path.as_ref());
+                let mut split_window = SplitWindow::new()
+                    .detached()
+                    .target_pane(&pane_id)
+                    .start_directory(pane_path.to_string_lossy())
+                    .print()
+                    .format(PANE_FORMAT);
+                split_window = match pane.split {
+                    LayoutSplit::Horizontal => split_window.horizontal(),
+                    LayoutSplit::Vertical => split_window.vertical(),
+                };
+                if let Some(size) = &pane.size {
+                    split_window = split_window.size(size);
+                }
+                pane_id = Tmux::with_command(split_window)
+                    .output()?
+                    .to_string()
+                    .trim()
+                    .to_string();
+                send_command(&pane_id, pane.command.as_ref())?;
+                if pane.focus {
+                    pane_ids_to_focus.push(pane_id.clone());
+                }
+            }
+
+            if let Some(layout_name) = &window.layout {
+                Tmux::with_command(
+                    SelectLayout::new()
+                        .target_pane(&window_id)
+                        .layout_name(layout_name),
+                )
+                .status()?;
+            }
+            if window.focus || window_id_to_focus_opt.is_none() {
+                window_id_to_focus_opt = Some(window_id);
+            }
+        }
+
+        let mut tmux_cmds = TmuxCommands::new();
+        for initial_window_id in &initial_window_ids {
+            tmux_cmds.push(KillWindow::new().target_window(initial_window_id));
+        }
+        tmux_cmds.push(MoveWindow::new().renumber().dst_window(&self.id));
+        for pane_id_to_focus in &pane_ids_to_focus {
+            tmux_cmds.push(SelectPane::new().target_pane(pane_id_to_focus));
+        }
+        if let Some(window_id_to_focus) = &window_id_to_focus_opt {
+            tmux_cmds.push(SelectWindow::new().target_window(window_id_to_focus));
+        }
+        Tmux::with_commands(tmux_cmds).status()?;
+
+        Ok(())
+    }
+}
+
+/// Returns the sanitized session name, suffixed with a number if a session
+/// with that name already exists, or `None`, if the name is empty, so tmux
+/// chooses the name.
+fn get_available_name(name: &str) -> anyhow::Result<Option<String>> {
+    let name = sanitize_name(name);
+    if name.is_empty() {
+        return Ok(None);
+    }
+    let output = Tmux::with_command(ListSessions::new().format("#{session_name}")).output()?;
+    let names = output.to_string();
+    Ok(Some(available_name(
+        &name,
+        &names.lines().collect::<Vec<_>>(),
+    )))
+}
+
+fn sanitize_name(name: &str) -> String {
+    // Tmux does not allow `.` and `:` in session names, since they are used as
+    // separators in targets.
+    name.trim()
+        .chars()
+        .map(|c| if matches!(c, '.' | ':') { '_' } else { c })
+        .collect()
+}
+
+fn available_name(name: &str, names: &[&str]) -> String {
+    let mut name_available = name.to_string();
+    let mut suffix = 1;
+    while names.contains(&name_available.as_str()) {
+        suffix += 1;
+        name_available = format!("{name}-{suffix}");
+    }
+    name_available
+}
+
+fn send_command(pane_id: &str, command_opt: Option<&String>) -> anyhow::Result<()> {
+    let Some(command) = command_opt else {
+        return Ok(());
+    };
+    let mut tmux_cmds = TmuxCommands::new();
+    tmux_cmds.push(
+        SendKeys::new()
+            .disable_lookup()
+            .target_pane(pane_id)
+            .key(command),
+    );
+    tmux_cmds.push(SendKeys::new().target_pane(pane_id).key("Enter"));
+    Tmux::with_commands(tmux_cmds).status()?;
+    Ok(())
 }
 
 impl PartialEq for Session {
@@ -150,3 +477,55 @@ impl Ord for Session {
         self.path.cmp(&other.path)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_unescapes_fields() -> anyhow::Result<()> {
+        let session = Session::parse(
+            "\\$12 my\\ session /home/user/my\\ project /home/user/my\\ project 2 1 30 10 20\n",
+        )?;
+        assert_eq!(session.id(), "$12");
+        assert_eq!(session.name(), "my session");
+        assert_eq!(session.path(), Path::new("/home/user/my project"));
+        assert_eq!(session.tmux_path, Path::new("/home/user/my project"));
+        assert_eq!(session.windows(), 2);
+        assert_eq!(session.attached(), 1);
+        assert_eq!(session.activity(), 30);
+        assert_eq!(session.created(), 10);
+        assert_eq!(session.last_attached(), 20);
+        Ok(())
+    }
+
+    #[test]
+    fn parse_keeps_escaped_quotes_and_backslashes() -> anyhow::Result<()> {
+        let session = Session::parse("\\$1 a\\\"b\\\\c /tmp /tmp 1 0 0 0 0")?;
+        assert_eq!(session.name(), "a\"b\\c");
+        Ok(())
+    }
+
+    #[test]
+    fn parse_accepts_empty_path_option() -> anyhow::Result<()> {
+        let session = Session::parse("\\$3 other  /tmp 1 0 0 0 0")?;
+        assert_eq!(session.name(), "other");
+        assert_eq!(session.path(), Path::new(""));
+        assert_eq!(session.tmux_path, Path::new("/tmp"));
+        Ok(())
+    }
+
+    #[test]
+    fn names_are_sanitized_and_suffixed_if_taken() {
+        assert_eq!(sanitize_name(" my.project:1 "), "my_project_1");
+        assert_eq!(available_name("1", &["0", "2"]), "1");
+        assert_eq!(available_name("foo", &["foo", "foo-2"]), "foo-3");
+    }
+
+    #[test]
+    fn parse_rejects_wrong_field_count() {
+        assert!(Session::parse("\\$1 name /tmp /tmp 1 0 0 0").is_err());
+        assert!(Session::parse("\\$1 name /tmp /tmp 1 0 0 0 0 0").is_err());
+        assert!(Session::parse("").is_err());
+    }
+}
@@ -0,0 +1,73 @@
+use std::{
+    fmt::Write as _,
+    io::{IsTerminal as _, Write as _},
+    path::{Path, PathBuf},
+    sync::LazyLock,
+};
+
+use ahash::HashMap;
+use ratatui::crossterm::style::Stylize as _;
+use sha2::{Digest as _, Sha256};
+
+use crate::{APP_NAME, json_store};
+
+static TRUSTED_FILES_FILE_PATH: LazyLock<PathBuf> = LazyLock::new(|| {
+    dirs::data_dir()
+        .unwrap_or_default()
+        .join(APP_NAME)
+        .join("trusted_files.json")
+});
+
+/// Checks whether the file at the given path with the given content has been
+/// trusted before and asks the user to trust it otherwise, if stdin is a
+/// terminal.
+pub fn check(path: &Path, content: &str) -> anyhow::Result<bool> {
+    let trusted_files = read()?;
+    if trusted_files.get(path) == Some(&hash(content)) {
+        return Ok(true);
+    }
+
+    if !std::io::stdin().is_terminal() {
+        eprintln!(
+            "{} ignoring untrusted file '{}', run `{APP_NAME} trust` in its directory to trust it",
+            "warning:".dark_yellow().bold(),
+            path.to_string_lossy()
+        );
+        return Ok(false);
+    }
+    eprint!(
+        "{} file '{}' is not trusted yet, do you want to trust it? [y/N] ",
+        "warning:".dark_yellow().bold(),
+        path.to_string_lossy()
+    );
+    std::io::stderr().flush()?;
+    let mut answer = String::new();
+    std::io::stdin().read_line(&mut answer)?;
+    if !matches!(answer.trim(), "y" | "Y" | "yes") {
+        return Ok(false);
+    }
+
+    add(path, content)?;
+    Ok(true)
+}
+
+pub fn add(path: &Path, content: &str) -> anyhow::Result<()> {
+    let mut trusted_files = read()?;
+    trusted_files.insert(path.to_path_buf(), hash(content));
+    json_store::write(&TRUSTED_FILES_FILE_PATH, &trusted_files)
+}
+
+/// Reads the hashes of the trusted files, which are all untrusted again, if
+/// the file is corrupt.
+fn read() -> anyhow::Result<HashMap<PathBuf, String>> {
+    json_store::read(&TRUSTED_FILES_FILE_PATH)
+}
+
+fn hash(content: &str) -> String {
+    Sha256::digest(content)
+        .iter()
+        .fold(String::new(), |mut hash, byte| {
+            let _ = write!(hash, "{byte:02x}");
+            hash
+        })
+}